@@ -0,0 +1,237 @@
+use super::config::OverflowPolicy;
+use super::record::Record;
+use super::sink::Dispatcher;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+// Record waiting to be written by the writer thread, with the sinks of the logger that sent it
+#[derive(Debug)]
+pub(crate) struct Entry {
+    pub(crate) dispatcher: Arc<Dispatcher>,
+    pub(crate) record: Record,
+}
+
+#[derive(Debug)]
+struct Queue {
+    entries: VecDeque<Entry>,
+    busy: bool,     // The writer thread is writing an entry taken from the queue
+    closed: bool,   // No more entries are accepted, the writer thread stops once the queue is empty
+    dropped: usize, // Number of entries dropped by the overflow policy
+    lost: usize,    // Number of entries left in the queue when the writer thread panicked
+}
+
+#[derive(Debug)]
+struct Shared {
+    queue: Mutex<Queue>,
+    not_empty: Condvar, // Wakes the writer thread
+    not_full: Condvar,  // Wakes callers blocked by a full queue
+    idle: Condvar,      // Wakes callers waiting for the queue to be drained
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// Closes the queue if a sink or a formatter panics on the writer thread, so the callers
+// waiting for it are woken up and the next entries are written on the callers' threads
+struct PanicGuard<'a>(&'a Shared);
+
+impl Drop for PanicGuard<'_> {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+
+        let mut queue = self.0.lock();
+        queue.busy = false;
+        queue.closed = true;
+        queue.lost += queue.entries.len();
+        queue.entries.clear();
+        self.0.not_full.notify_all();
+        self.0.idle.notify_all();
+    }
+}
+
+/// Result of sending an entry to the writer thread
+pub(crate) enum SendResult {
+    Queued,
+    Dropped,
+    Closed(Box<Entry>), // The writer was shut down, the entry must be written by the caller
+}
+
+/// Writes the messages on a dedicated thread, the callers only push them to a bounded queue
+#[derive(Debug)]
+pub(crate) struct AsyncWriter {
+    shared: Arc<Shared>,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl AsyncWriter {
+    pub(crate) fn new(
+        capacity: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> Result<Self, std::io::Error> {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                entries: VecDeque::with_capacity(capacity),
+                busy: false,
+                closed: false,
+                dropped: 0,
+                lost: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            idle: Condvar::new(),
+        });
+
+        let thread_shared = Arc::clone(&shared);
+        let handle = std::thread::Builder::new()
+            .name("exec_logger-writer".to_string())
+            .spawn(move || AsyncWriter::run(&thread_shared))?;
+
+        Ok(AsyncWriter {
+            shared,
+            capacity,
+            overflow_policy,
+            handle: Mutex::new(Some(handle)),
+        })
+    }
+
+    // Loop of the writer thread
+    fn run(shared: &Shared) {
+        let _guard = PanicGuard(shared);
+        loop {
+            let entry = {
+                let mut queue = shared.lock();
+                while queue.entries.is_empty() && !queue.closed {
+                    queue = shared
+                        .not_empty
+                        .wait(queue)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+
+                match queue.entries.pop_front() {
+                    Some(entry) => {
+                        queue.busy = true;
+                        shared.not_full.notify_one();
+                        entry
+                    }
+                    None => break, // The queue is closed and empty
+                }
+            };
+
+            entry.dispatcher.write(&entry.record);
+
+            let mut queue = shared.lock();
+            queue.busy = false;
+            if queue.entries.is_empty() {
+                shared.idle.notify_all();
+            }
+        }
+
+        shared.idle.notify_all();
+    }
+
+    /// Push an entry to the queue, applying the overflow policy if the queue is full
+    pub(crate) fn send(&self, entry: Entry) -> SendResult {
+        let mut queue = self.shared.lock();
+
+        while !queue.closed && queue.entries.len() >= self.capacity {
+            match &self.overflow_policy {
+                OverflowPolicy::Block => {}
+                OverflowPolicy::DropNewest => {
+                    queue.dropped += 1;
+                    return SendResult::Dropped;
+                }
+                OverflowPolicy::DropOldest => {
+                    queue.entries.pop_front();
+                    queue.dropped += 1;
+                    continue;
+                }
+                OverflowPolicy::DropBelow(level) => {
+                    if entry.record.get_level() < level {
+                        queue.dropped += 1;
+                        return SendResult::Dropped;
+                    }
+
+                    // Make room by discarding the oldest queued message below the level
+                    let lower = queue
+                        .entries
+                        .iter()
+                        .position(|x| x.record.get_level() < level);
+                    if let Some(index) = lower {
+                        queue.entries.remove(index);
+                        queue.dropped += 1;
+                        continue;
+                    }
+                }
+            }
+
+            queue = self
+                .shared
+                .not_full
+                .wait(queue)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+
+        if queue.closed {
+            return SendResult::Closed(Box::new(entry));
+        }
+
+        queue.entries.push_back(entry);
+        self.shared.not_empty.notify_one();
+        SendResult::Queued
+    }
+
+    /// Wait until every queued entry is written
+    pub(crate) fn wait_idle(&self) {
+        let mut queue = self.shared.lock();
+        while !queue.entries.is_empty() || queue.busy {
+            queue = self
+                .shared
+                .idle
+                .wait(queue)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Write the remaining entries and stop the writer thread
+    pub(crate) fn shutdown(&self) {
+        {
+            let mut queue = self.shared.lock();
+            queue.closed = true;
+            self.shared.not_empty.notify_all();
+            self.shared.not_full.notify_all();
+        }
+
+        let handle = self.handle.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+        if let Some(handle) = handle {
+            if handle.join().is_err() {
+                eprintln!(
+                    "Log writer thread panicked, {} queued log messages were lost",
+                    self.shared.lock().lost
+                );
+            }
+
+            let dropped = self.shared.lock().dropped;
+            if dropped > 0 {
+                eprintln!(
+                    "{} log messages were dropped because the log queue was full",
+                    dropped
+                );
+            }
+        }
+    }
+}
+
+impl Drop for AsyncWriter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
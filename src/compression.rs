@@ -0,0 +1,130 @@
+use super::config::{CompressionFormat, CompressionMode, CompressionPolicy};
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+impl CompressionFormat {
+    /// Extension added to the compressed files
+    pub fn extension(&self) -> &str {
+        match self {
+            CompressionFormat::Gzip => "gz",
+            CompressionFormat::Zstd => "zst",
+        }
+    }
+}
+
+// Wrap a writer with the encoder of the format
+fn encoder<'a, W: Write + 'a>(
+    writer: W,
+    format: &CompressionFormat,
+) -> Result<Box<dyn FinishWrite + 'a>, std::io::Error> {
+    match format {
+        CompressionFormat::Gzip => Ok(Box::new(GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        ))),
+        CompressionFormat::Zstd => Ok(Box::new(zstd::Encoder::new(writer, 0)?)),
+    }
+}
+
+// Encoders must be finished to write the end of the compressed stream
+trait FinishWrite: Write {
+    fn finish_write(self: Box<Self>) -> Result<(), std::io::Error>;
+}
+
+impl<W: Write> FinishWrite for GzEncoder<W> {
+    fn finish_write(self: Box<Self>) -> Result<(), std::io::Error> {
+        self.finish()?.flush()
+    }
+}
+
+impl<W: Write> FinishWrite for zstd::Encoder<'_, W> {
+    fn finish_write(self: Box<Self>) -> Result<(), std::io::Error> {
+        self.finish()?.flush()
+    }
+}
+
+// Append an extension to the path, e.g. execution_log.txt -> execution_log.txt.gz
+fn with_added_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut file_name = path.as_os_str().to_os_string();
+    file_name.push(".");
+    file_name.push(extension);
+    PathBuf::from(file_name)
+}
+
+/// Compress the folder of an execution according to the compression mode
+///
+/// CompressionMode::Archive replaces the folder by a single archive (e.g. "2024-01-01 10_00_00.tar.gz")
+/// CompressionMode::PerFile compresses each file inside the folder (e.g. "execution_log.txt.gz")
+pub(crate) fn compress_execution(
+    folder: &Path,
+    compression: &CompressionPolicy,
+) -> Result<(), std::io::Error> {
+    match compression.mode {
+        CompressionMode::Archive => compress_archive(folder, &compression.format),
+        CompressionMode::PerFile => compress_files(folder, &compression.format),
+    }
+}
+
+fn compress_archive(folder: &Path, format: &CompressionFormat) -> Result<(), std::io::Error> {
+    let folder_name = match folder.file_name() {
+        Some(x) => x.to_owned(),
+        None => return Ok(()),
+    };
+
+    let archive_path = with_added_extension(folder, &format!("tar.{}", format.extension()));
+    // Write to a temporary file, so an interrupted compression never leaves a partial archive
+    // with a name that the housekeeping recognizes as an execution
+    let temporary_path = with_added_extension(&archive_path, "tmp");
+
+    let result = (|| {
+        let file = BufWriter::new(File::create(&temporary_path)?);
+        let mut builder = tar::Builder::new(encoder(file, format)?);
+        builder.append_dir_all(&folder_name, folder)?;
+        builder.into_inner()?.finish_write()
+    })();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temporary_path);
+        return Err(e);
+    }
+
+    std::fs::rename(&temporary_path, &archive_path)?;
+    std::fs::remove_dir_all(folder)
+}
+
+fn compress_files(folder: &Path, format: &CompressionFormat) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(folder)?.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        // Skip files compressed by a previous housekeeping
+        let extension = path.extension().and_then(|x| x.to_str());
+        if extension == Some("gz") || extension == Some("zst") || extension == Some("tmp") {
+            continue;
+        }
+
+        let compressed_path = with_added_extension(&path, format.extension());
+        let temporary_path = with_added_extension(&compressed_path, "tmp");
+
+        let result = (|| {
+            let mut reader = BufReader::new(File::open(&path)?);
+            let mut writer = encoder(BufWriter::new(File::create(&temporary_path)?), format)?;
+            std::io::copy(&mut reader, &mut writer)?;
+            writer.finish_write()
+        })();
+
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&temporary_path);
+            return Err(e);
+        }
+
+        std::fs::rename(&temporary_path, &compressed_path)?;
+        std::fs::remove_file(&path)?;
+    }
+
+    Ok(())
+}
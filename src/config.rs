@@ -1,10 +1,16 @@
+pub use super::config_builder::LoggerConfigurationBuilder;
+use super::formatter::Formatter;
 use super::log_level::LogLevel;
+use super::sink::{Sink, SinkEntry};
+use super::template::{Keyword, Template};
 use chrono::Local;
 use core::fmt;
 use std::ffi::OsStr;
 use std::fmt::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use whoami::{self, fallible};
 
 const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%z";
@@ -14,30 +20,270 @@ const DEFAULT_MESSAGE_FORMAT: &str =
 #[derive(Debug)]
 pub enum ConfigError {
     InvalidFormat(String),
+    InvalidValue(String),
+    UnknownKeyword { keyword: String, position: usize }, // Keyword of the message format
+    Multiple(Vec<ConfigError>), // Every error found by LoggerConfigurationBuilder::build
+}
+
+impl ConfigError {
+    // The error itself if there is only one
+    pub(crate) fn from_errors(mut errors: Vec<ConfigError>) -> ConfigError {
+        if errors.len() == 1 {
+            errors.remove(0)
+        } else {
+            ConfigError::Multiple(errors)
+        }
+    }
 }
 
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ConfigError::InvalidFormat(details) => write!(f, "Invalid format: {}", details),
+            ConfigError::InvalidValue(details) => write!(f, "Invalid value: {}", details),
+            ConfigError::UnknownKeyword { keyword, position } => write!(
+                f,
+                "Unknown keyword {{{}}} at position {} of the message format",
+                keyword, position
+            ),
+            ConfigError::Multiple(errors) => {
+                let errors = errors
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join("; ");
+                write!(f, "Invalid configuration: {}", errors)
+            }
         }
     }
 }
 
 impl std::error::Error for ConfigError {}
 
+/// Defines how the messages are written
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OutputFormat {
+    Text,      // Message format with keywords, see LoggerConfiguration::set_message_format
+    JsonLines, // One JSON object per line
+}
+
+/// Filter level of the records sent from a target, see LoggerConfiguration::set_directives
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Directive {
+    pub target: String,          // Module path prefix, empty for every target
+    pub level: Option<LogLevel>, // None disables the target ("off")
+}
+
+impl Directive {
+    /// Check if the directive applies to a target, `a::b` applies to `a::b` and `a::b::c` but not to `a::bc`
+    pub fn matches(&self, target: &str) -> bool {
+        if self.target.is_empty() {
+            return true;
+        }
+        match target.strip_prefix(self.target.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with("::"),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match &self.level {
+            Some(level) => level.to_string().to_lowercase(),
+            None => "off".to_string(),
+        };
+        if self.target.is_empty() {
+            write!(f, "{}", level)
+        } else {
+            write!(f, "{}={}", self.target, level)
+        }
+    }
+}
+
+/// Defines where the console messages are printed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleTarget {
+    Stdout,          // Every message is printed to stdout
+    Stderr,          // Every message is printed to stderr
+    Split(LogLevel), // Messages with severity higher or equal to the level go to stderr, the others to stdout
+    Disabled,        // Nothing is printed, e.g. for daemons
+}
+
+/// Defines when the console messages are colored
+///
+/// The log file and the sinks added with add_sink never contain colors
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ColorMode {
+    Auto,   // Colored if the console is a terminal and the NO_COLOR environment variable is not set
+    Always, // Always colored
+    Never,  // Never colored
+}
+
+/// ANSI color of the console messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    Fixed(u8), // Color of the 256 colors palette
+}
+
+/// Defines when the buffered messages are written to the log file
+///
+/// The log file is flushed when a Logger is dropped or when the global logger is initialized
+/// again. **The global logger is never dropped**: with a policy other than EveryLine, call
+/// `log::flush` or `log::shutdown` before the process exits, otherwise the last buffered
+/// messages are lost
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FlushPolicy {
+    EveryLine,          // Flush after every message
+    Interval(Duration), // Flush at least once per interval, from a background thread
+    Severity(LogLevel), // Flush after messages with severity higher or equal to the level
+}
+
+/// Defines when the log file is rotated inside the execution folder
+///
+/// When rotated, execution_log.txt is renamed to execution_log.1.txt, the previous
+/// execution_log.1.txt is renamed to execution_log.2.txt and so on
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RotationPolicy {
+    Never,         // The execution has a single log file
+    MaxBytes(u64), // Rotate before the file exceeds the number of bytes
+    Hourly,        // Rotate when the hour changes (local timezone)
+    Daily,         // Rotate when the day changes (local timezone)
+}
+
+/// Algorithm used to compress old executions
+#[cfg(feature = "compression")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CompressionFormat {
+    Gzip,
+    Zstd,
+}
+
+/// Defines how the folder of an old execution is compressed
+#[cfg(feature = "compression")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CompressionMode {
+    PerFile, // Each file in the folder is compressed, e.g. execution_log.txt.gz
+    Archive, // The folder is replaced by a single tar archive, e.g. "2024-01-01 10_00_00.tar.gz"
+}
+
+/// Defines which old executions are compressed during housekeeping
+///
+/// An execution is compressed if it is older than `after_days` or if it is not one of the
+/// newest `after_executions` executions (the current execution included).
+/// Executions are still deleted according to `days_stored` and `executions_stored`
+#[cfg(feature = "compression")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CompressionPolicy {
+    pub format: CompressionFormat,
+    pub mode: CompressionMode,
+    pub after_days: Option<u32>, // Compress executions older than X days
+    pub after_executions: Option<u32>, // Compress executions exceeding X executions
+}
+
+/// Defines what happens to new messages when the queue of the asynchronous writer is full
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    Block,      // Wait until there is space in the queue
+    DropNewest, // Discard the new message
+    DropOldest, // Discard the oldest message in the queue
+    // Discard the new message if its severity is lower than the level. Otherwise discard the
+    // oldest queued message below the level, and wait only if there is none
+    DropBelow(LogLevel),
+}
+
+/// Defines on which thread the messages are written to the console and to the log file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WriteMode {
+    Sync, // Messages are written on the thread that sends them
+    Async {
+        capacity: usize, // Maximum number of messages waiting to be written
+        overflow_policy: OverflowPolicy,
+    }, // Messages are sent to a bounded queue and written by a dedicated thread
+}
+
+/// Configuration of the logger
+///
+/// With the `serde` feature, the configuration can be loaded from a file and serialized, see
+/// `from_file`. The sinks added with add_sink and the formatter are not serialized
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(
+        try_from = "crate::config_file::ConfigFile",
+        into = "crate::config_file::ConfigFile"
+    )
+)]
 pub struct LoggerConfiguration {
-    log_dir: PathBuf,                   // root directory of log folders
-    file_extension: String,             // extension of log file
-    days_stored: Option<u32>,           // Number of days to keep
-    executions_stored: Option<u32>,     // Number of executions/folders to keep
-    filter_log_level: Option<LogLevel>, // Lowest severity that will be show
-    exe_name: String,                   // Name of the executable
-    system_name: String,                // Name of the system
-    user_name: String,                  // Name of the user (with domain if present)
-    message_format: Option<String>,     // Format of message written to log file
-    timestamp_format: Option<String>,   // Format of timestamp if present in message_format
+    log_dir: PathBuf,                      // root directory of log folders
+    file_extension: String,                // extension of log file
+    days_stored: Option<u32>,              // Number of days to keep
+    executions_stored: Option<u32>,        // Number of executions/folders to keep
+    filter_log_level: Option<LogLevel>,    // Lowest severity of the sinks without their own level
+    directives: Vec<Directive>,            // Filter levels of specific targets
+    console_log_level: Option<LogLevel>,   // Lowest severity printed to the console
+    console_target: ConsoleTarget,         // Where the console messages are printed
+    color_mode: ColorMode,                 // When the console messages are colored
+    level_colors: Vec<(LogLevel, Color)>,  // Color of the console messages of each level
+    custom_levels: Vec<LogLevel>, // Custom levels that can be used by name in the configuration
+    file_log_level: Option<LogLevel>, // Lowest severity written to the log file
+    exe_name: String,             // Name of the executable
+    system_name: String,          // Name of the system
+    user_name: String,            // Name of the user (with domain if present)
+    message_format: Option<String>, // Format of message written to log file
+    message_template: Template,   // message_format parsed by set_message_format
+    formatter: Option<Arc<dyn Formatter>>, // Used in place of message_format if present
+    timestamp_format: Option<String>, // Format of timestamp if present in message_format
+    output_format: OutputFormat,  // Text or structured output
+    flush_policy: FlushPolicy,    // When the buffered messages are written to the log file
+    write_mode: WriteMode,        // Thread on which the messages are written
+    rotation_policy: RotationPolicy, // When the log file is rotated inside the execution folder
+    max_rotated_files: Option<u32>, // Number of rotated files to keep
+    max_total_bytes: Option<u64>, // Disk usage limit of all executions
+    sinks: Vec<SinkEntry>,        // Destinations added to the console and the log file
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionPolicy>, // Which old executions are compressed
 }
 
 impl LoggerConfiguration {
@@ -81,36 +327,133 @@ impl LoggerConfiguration {
             days_stored,
             executions_stored,
             filter_log_level,
+            directives: Vec::new(),
+            console_log_level: None,
+            console_target: ConsoleTarget::Stdout,
+            color_mode: ColorMode::Auto,
+            custom_levels: Vec::new(),
+            level_colors: vec![
+                (LogLevel::Error, Color::Red),
+                (LogLevel::Warn, Color::Yellow),
+                (LogLevel::Info, Color::Green),
+                (LogLevel::Debug, Color::Cyan),
+                (LogLevel::Trace, Color::BrightBlack),
+            ],
+            file_log_level: None,
             exe_name,
             system_name,
             user_name,
             message_format: None,
+            message_template: Template::parse(DEFAULT_MESSAGE_FORMAT)
+                .expect("The default message format is valid"),
+            formatter: None,
             timestamp_format: None,
+            output_format: OutputFormat::Text,
+            flush_policy: FlushPolicy::EveryLine,
+            write_mode: WriteMode::Sync,
+            rotation_policy: RotationPolicy::Never,
+            max_rotated_files: None,
+            max_total_bytes: None,
+            sinks: Vec::new(),
+            #[cfg(feature = "compression")]
+            compression: None,
         }
     }
 
+    /// Create a configuration from environment variables, the missing or empty variables keep
+    /// the default values
+    ///
+    /// Variables read, e.g. with the prefix `EXEC_LOGGER`:
+    /// EXEC_LOGGER_DIR - Root directory of the log folders
+    /// EXEC_LOGGER_LEVEL - Filter level (e.g. `info`) or directives (e.g. `warn,my_crate::db=trace`)
+    /// EXEC_LOGGER_DAYS - Number of days to keep
+    /// EXEC_LOGGER_EXECUTIONS - Number of executions to keep
+    /// EXEC_LOGGER_FORMAT - Message format, see set_message_format
+    /// EXEC_LOGGER_TIMESTAMP_FORMAT - Timestamp format, see set_timestamp_format
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// let config = LoggerConfiguration::from_env("EXEC_LOGGER").unwrap();
+    /// ```
+    pub fn from_env(prefix: &str) -> Result<Self, ConfigError> {
+        let mut config = LoggerConfiguration::default();
+
+        if let Some(dir) = env_var(prefix, "DIR")? {
+            config.log_dir = PathBuf::from(dir);
+        }
+
+        if let Some(level) = env_var(prefix, "LEVEL")? {
+            match config.parse_level(level.trim()) {
+                Ok(level) => config.set_filter_level(level),
+                Err(_) => config.set_directives(&level)?,
+            }
+        }
+
+        if let Some(days) = env_var(prefix, "DAYS")? {
+            config.days_stored = Some(parse_env_number(prefix, "DAYS", &days)?);
+        }
+
+        if let Some(executions) = env_var(prefix, "EXECUTIONS")? {
+            config.executions_stored = Some(parse_env_number(prefix, "EXECUTIONS", &executions)?);
+        }
+
+        check_storage(
+            &config.file_extension,
+            config.days_stored,
+            config.executions_stored,
+        )
+        .map_err(ConfigError::from_errors)?;
+
+        if let Some(format) = env_var(prefix, "FORMAT")? {
+            config.set_message_format(&format)?;
+        }
+
+        if let Some(format) = env_var(prefix, "TIMESTAMP_FORMAT")? {
+            config.set_timestamp_format(&format)?;
+        }
+
+        Ok(config)
+    }
+
     /// Used to filter LogLevels that are logged
-    /// Any LogLevel whose severity is lower than the `filter_level` will be ignored during logging
+    /// The `filter_level` is the lowest severity written by the sinks without their own level
+    /// (see set_console_level, set_file_level and add_sink), for the records that match no
+    /// directive with a target
     /// The severity is a i32 value and higher values represent more severe information
     ///
     /// # Examples
     ///
     /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// # use exec_logger::log_level::LogLevel;
     /// let mut config = LoggerConfiguration::default();
-    /// config.set_filter_level(&LogLevel::Info); // The default LogLevels would be ignored: DEBUG, TRACE
+    /// config.set_filter_level(LogLevel::Info); // The default LogLevels would be ignored: DEBUG, TRACE
     /// ```
     ///
     /// # Notes
     ///
     /// Severity can be checked using i32::from() on a &LogLevel variant
     /// ```rust
+    /// # use exec_logger::log_level::LogLevel;
     /// let level = LogLevel::Error;
     /// let severity = i32::from(&level);
     /// println!("{} severity = {}", level, severity);
     /// ```
     ///
+    /// A directive without target set by set_directives is replaced by the filter level
     pub fn set_filter_level(&mut self, filter_level: LogLevel) {
         self.filter_log_level = Some(filter_level);
+        self.directives.retain(|x| !x.target.is_empty());
+    }
+
+    /// Write every message that matches no directive with a target, as if no filter level was given to new
+    ///
+    /// A directive without target set by set_directives is removed
+    pub fn clear_filter_level(&mut self) {
+        self.filter_log_level = None;
+        self.directives.retain(|x| !x.target.is_empty());
     }
 
     /// Return the LogLevel used to filter log messages
@@ -118,6 +461,240 @@ impl LoggerConfiguration {
         self.filter_log_level.clone()
     }
 
+    /// Define the filter levels per target with `RUST_LOG` style directives
+    ///
+    /// The directives are separated by commas, each one is `target=level` or just `level`
+    /// for every target. The level is error, warn, info, debug, trace or off. The directive
+    /// with the longest matching target is checked before the levels of the sinks.
+    /// The directive without target is the same as the filter level: records that match no
+    /// other directive use it in the sinks without their own level.
+    ///
+    /// The target of the records is the module path when sent with the macros, the target of the
+    /// `log` crate records or the target of the `tracing` events.
+    /// The records sent with the functions and methods, e.g. `log::info` or `Logger::info_kv`,
+    /// have an empty target and no module: only the directive without target applies to them
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_directives("warn,my_crate::db=trace,hyper=off").unwrap();
+    /// ```
+    pub fn set_directives(&mut self, directives: &str) -> Result<(), ConfigError> {
+        let mut parsed = Vec::new();
+        for directive in directives
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+        {
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) => (target.trim(), level.trim()),
+                None => ("", directive),
+            };
+
+            let level = if level.eq_ignore_ascii_case("off") {
+                None
+            } else {
+                match self.parse_level(level) {
+                    Ok(x) => Some(x),
+                    Err(e) => {
+                        eprintln!("{}, the directives are unchanged", e);
+                        return Err(ConfigError::InvalidFormat(e));
+                    }
+                }
+            };
+
+            parsed.push(Directive {
+                target: target.to_string(),
+                level,
+            });
+        }
+
+        self.directives = parsed;
+        Ok(())
+    }
+
+    /// Return the directives set by set_directives
+    pub fn get_directives(&self) -> Vec<Directive> {
+        self.directives.clone()
+    }
+
+    /// Declare a custom level, so it can be used by name in set_directives and in configuration files
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// let mut config = LoggerConfiguration::default();
+    /// let audit = config.add_custom_level("AUDIT", 45);
+    /// config.set_directives("audit,my_crate::db=trace").unwrap();
+    /// assert_eq!(config.get_custom_level("audit"), Some(audit));
+    /// ```
+    pub fn add_custom_level(&mut self, name: &str, severity: i32) -> LogLevel {
+        let level = LogLevel::Custom(severity, name.to_string());
+        self.custom_levels
+            .retain(|x| !x.to_string().eq_ignore_ascii_case(name));
+        self.custom_levels.push(level.clone());
+        level
+    }
+
+    /// Return the custom level declared with the name, case insensitive
+    pub fn get_custom_level(&self, name: &str) -> Option<LogLevel> {
+        self.custom_levels
+            .iter()
+            .find(|x| x.to_string().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// Return the custom levels declared with add_custom_level
+    pub fn get_custom_levels(&self) -> Vec<LogLevel> {
+        self.custom_levels.clone()
+    }
+
+    /// Parse the name of a default level or of a declared custom level
+    pub(crate) fn parse_level(&self, name: &str) -> Result<LogLevel, String> {
+        match self.get_custom_level(name) {
+            Some(level) => Ok(level),
+            None => name.parse::<LogLevel>(),
+        }
+    }
+
+    /// Copy the options that can change while the logger is running: levels, directives,
+    /// console output and formats. The log directory, the housekeeping, the writers, the
+    /// sinks added with add_sink and the formatter are kept
+    pub(crate) fn apply_reloadable(&mut self, other: &LoggerConfiguration) {
+        self.filter_log_level = other.filter_log_level.clone();
+        self.directives = other.directives.clone();
+        self.custom_levels = other.custom_levels.clone();
+        self.console_log_level = other.console_log_level.clone();
+        self.file_log_level = other.file_log_level.clone();
+        self.console_target = other.console_target.clone();
+        self.color_mode = other.color_mode.clone();
+        self.level_colors = other.level_colors.clone();
+        self.message_format = other.message_format.clone();
+        self.message_template = other.message_template.clone();
+        self.timestamp_format = other.timestamp_format.clone();
+        self.output_format = other.output_format.clone();
+    }
+
+    /// Return the directive with the longest target matching the target, the directive without
+    /// target is not returned, it is used like the filter level
+    pub(crate) fn get_target_directive(&self, target: &str) -> Option<&Directive> {
+        self.directives
+            .iter()
+            .filter(|x| !x.target.is_empty() && x.matches(target))
+            .max_by_key(|x| x.target.len())
+    }
+
+    /// Return the directive without target
+    pub(crate) fn get_default_directive(&self) -> Option<&Directive> {
+        self.directives.iter().find(|x| x.target.is_empty())
+    }
+
+    /// Define the lowest severity printed to the console
+    ///
+    /// The console level replaces the filter level for the console, so the console can be more
+    /// verbose than the log file. The directives with a target still apply to the console.
+    /// None prints every message that passes the filter level
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// # use exec_logger::log_level::LogLevel;
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_filter_level(LogLevel::Debug);
+    /// config.set_console_level(Some(LogLevel::Warn)); // DEBUG in the log file, WARN on the console
+    /// ```
+    pub fn set_console_level(&mut self, console_level: Option<LogLevel>) {
+        self.console_log_level = console_level;
+    }
+
+    /// Return the lowest severity printed to the console
+    pub fn get_console_level(&self) -> Option<LogLevel> {
+        self.console_log_level.clone()
+    }
+
+    /// Define where the console messages are printed, stdout by default
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{ConsoleTarget, LoggerConfiguration};
+    /// # use exec_logger::log_level::LogLevel;
+    /// let mut config = LoggerConfiguration::default();
+    /// // Keep stdout clean for the output of the program
+    /// config.set_console_target(ConsoleTarget::Split(LogLevel::Error));
+    /// ```
+    pub fn set_console_target(&mut self, console_target: ConsoleTarget) {
+        self.console_target = console_target;
+    }
+
+    /// Return where the console messages are printed
+    pub fn get_console_target(&self) -> ConsoleTarget {
+        self.console_target.clone()
+    }
+
+    /// Define when the console messages are colored, ColorMode::Auto by default
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) {
+        self.color_mode = color_mode;
+    }
+
+    /// Return when the console messages are colored
+    pub fn get_color_mode(&self) -> ColorMode {
+        self.color_mode.clone()
+    }
+
+    /// Define the color of the console messages of a level, None prints them without color
+    ///
+    /// The default levels have a color by default, custom levels have no color until one is set
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{Color, LoggerConfiguration};
+    /// # use exec_logger::log;
+    /// # use exec_logger::log_level::LogLevel;
+    /// let mut config = LoggerConfiguration::default();
+    /// let audit = log::create_custom_level("AUDIT", 45);
+    /// config.set_level_color(&audit, Some(Color::Magenta));
+    /// config.set_level_color(&LogLevel::Info, None);
+    /// ```
+    pub fn set_level_color(&mut self, level: &LogLevel, color: Option<Color>) {
+        self.level_colors.retain(|(x, _)| x != level);
+        if let Some(color) = color {
+            self.level_colors.push((level.clone(), color));
+        }
+    }
+
+    /// Return the color of the console messages of a level
+    pub fn get_level_color(&self, level: &LogLevel) -> Option<Color> {
+        self.level_colors
+            .iter()
+            .find(|(x, _)| x == level)
+            .map(|(_, color)| *color)
+    }
+
+    /// Return the color of each level
+    pub(crate) fn get_level_colors(&self) -> Vec<(LogLevel, Color)> {
+        self.level_colors.clone()
+    }
+
+    /// Define the lowest severity written to the log file
+    ///
+    /// The file level replaces the filter level for the log file, so the log file can be more
+    /// verbose than the console. The directives with a target still apply to the log file.
+    /// None writes every message that passes the filter level
+    pub fn set_file_level(&mut self, file_level: Option<LogLevel>) {
+        self.file_log_level = file_level;
+    }
+
+    /// Return the lowest severity written to the log file
+    pub fn get_file_level(&self) -> Option<LogLevel> {
+        self.file_log_level.clone()
+    }
+
     /// Return a String with the message format
     pub fn get_message_format(&self) -> &str {
         if let Some(x) = &self.message_format {
@@ -139,6 +716,22 @@ impl LoggerConfiguration {
     /// {USER_NAME}
     /// {LEVEL}
     /// {MESSAGE}
+    /// {TARGET} - Module path of the sender, see set_directives
+    /// {SPANS} - Spans of `tracing` events, joined by ':'
+    /// {FIELDS} - Extra fields of the message, as key=value separated by spaces
+    /// {CONTEXT} - Context fields of the thread (see log::with_context), as key=value separated by spaces
+    /// {THREAD_NAME} - Name of the thread that sent the message, <unnamed> if it has no name
+    /// {THREAD_ID} - Number of the thread that sent the message, from 1 in the order of their first message
+    /// {PID} - Id of the process
+    /// {EXECUTION_ID} - Name of the execution folder
+    /// {ELAPSED} - Seconds since the logger was initialized, with milliseconds
+    /// {FILE} - Source file of the call site
+    /// {LINE} - Line of the call site
+    /// {MODULE} - Module path of the call site, only known for the macros, the `log` crate and `tracing`
+    ///
+    /// A keyword can be padded with spaces to a width: {TARGET:<20} (left), {PID:>8} (right),
+    /// {LEVEL:^7} (centered) or {LINE:4} (left). {LEVEL} is padded to 7 characters if no width
+    /// is given, {LEVEL:0} removes the padding. Literal braces are written {{ and }}
     ///
     /// # Arguments
     ///
@@ -147,30 +740,79 @@ impl LoggerConfiguration {
     /// # Example
     ///
     /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
     /// let mut config = LoggerConfiguration::default();
-    /// config.set_message_format("{TIMESTAMP} | {LEVEL} | {MESSAGE}")
+    /// config.set_message_format("{TIMESTAMP} | {LEVEL} | {MESSAGE}").unwrap();
     /// ```
     ///
     /// # Notes
     ///
-    /// Only {LEVEL} and {MESSAGE} are obligatory
+    /// Only {LEVEL} and {MESSAGE} are obligatory. Unknown keywords are rejected with
+    /// ConfigError::UnknownKeyword, unmatched braces and invalid widths with ConfigError::InvalidFormat
     pub fn set_message_format(&mut self, format: &str) -> Result<(), ConfigError> {
-        if !format.contains("{MESSAGE}") {
+        let template = match Template::parse(format) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}. Message format is unchanged", e);
+                return Err(e);
+            }
+        };
+
+        if !template.contains(Keyword::Message) {
             eprintln!("Message format must contain {{MESSAGE}}. Message format is unchanged");
             Err(ConfigError::InvalidFormat(
                 "Message format must contain {MESSAGE}".to_string(),
             ))
-        } else if !format.contains("{LEVEL}") {
+        } else if !template.contains(Keyword::Level) {
             eprintln!("Message format must contain {{LEVEL}}. Message format is unchanged");
             Err(ConfigError::InvalidFormat(
                 "Message format must contain {LEVEL}".to_string(),
             ))
         } else {
             self.message_format = Some(format.to_string());
+            self.message_template = template;
             Ok(())
         }
     }
 
+    /// Return the message format parsed by set_message_format
+    pub(crate) fn get_message_template(&self) -> &Template {
+        &self.message_template
+    }
+
+    /// Render the text messages with a Formatter in place of the message format
+    ///
+    /// The formatter is used for every output whose format is OutputFormat::Text,
+    /// None restores the message format. See exec_logger::formatter::Formatter
+    ///
+    /// The formatter is not serialized and a reload keeps the formatter of the running logger
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// use exec_logger::formatter::{Formatter, FormatterRecord};
+    ///
+    /// struct CsvFormatter;
+    ///
+    /// impl Formatter for CsvFormatter {
+    ///     fn format(&self, record: &FormatterRecord, buffer: &mut String) {
+    ///         buffer.push_str(&format!("{},{}", record.get_level(), record.get_message()));
+    ///     }
+    /// }
+    ///
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_formatter(Some(Box::new(CsvFormatter)));
+    /// ```
+    pub fn set_formatter(&mut self, formatter: Option<Box<dyn Formatter>>) {
+        self.formatter = formatter.map(Arc::from);
+    }
+
+    /// Return the formatter set with set_formatter
+    pub(crate) fn get_formatter(&self) -> Option<&dyn Formatter> {
+        self.formatter.as_deref()
+    }
+
     /// Return a String with the timestamp format
     pub fn get_timestamp_format(&self) -> &str {
         if let Some(x) = &self.timestamp_format {
@@ -191,8 +833,9 @@ impl LoggerConfiguration {
     /// # Example
     ///
     /// ```rust
-    /// let config = LoggerConfiguration::default();
-    /// config.set_timestamp_format("%Y-%m-%d %H:%M:%S%z")
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_timestamp_format("%Y-%m-%d %H:%M:%S%z").unwrap();
     /// ```
     pub fn set_timestamp_format(&mut self, format: &str) -> Result<(), ConfigError> {
         // Use result to catch a panic when trying the format
@@ -210,6 +853,255 @@ impl LoggerConfiguration {
         }
     }
 
+    /// Define how the messages are written to the console and to the log file
+    ///
+    /// By default, the messages are written as text using the message format.
+    /// With OutputFormat::JsonLines each message is written as a JSON object in a single line,
+    /// with the fields "timestamp", "level", "severity", "exe", "host", "user" and "message".
+    /// Spans are written in the "spans" array and extra key/value fields in the "fields" object.
+    /// The timestamp uses the timestamp format and the message format is ignored
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{LoggerConfiguration, OutputFormat};
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_output_format(OutputFormat::JsonLines);
+    /// // {"timestamp":"2024-01-01 10:00:00+0000","level":"INFO","severity":30,"exe":"app",...}
+    /// ```
+    pub fn set_output_format(&mut self, output_format: OutputFormat) {
+        self.output_format = output_format;
+    }
+
+    /// Return the output format
+    pub fn get_output_format(&self) -> OutputFormat {
+        self.output_format.clone()
+    }
+
+    /// Define when the buffered messages are written to the log file
+    ///
+    /// By default, the log file is flushed after every message. With a buffered policy,
+    /// `log::flush` or `log::shutdown` must be called before the process exits, see FlushPolicy
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{FlushPolicy, LoggerConfiguration};
+    /// # use exec_logger::log_level::LogLevel;
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_flush_policy(FlushPolicy::Severity(LogLevel::Error)).unwrap();
+    /// ```
+    ///
+    /// The interval of FlushPolicy::Interval must be positive
+    pub fn set_flush_policy(&mut self, flush_policy: FlushPolicy) -> Result<(), ConfigError> {
+        if flush_policy == FlushPolicy::Interval(Duration::ZERO) {
+            eprintln!("Flush interval must be positive. Flush policy is unchanged");
+            Err(ConfigError::InvalidValue(
+                "Flush interval must be positive".to_string(),
+            ))
+        } else {
+            self.flush_policy = flush_policy;
+            Ok(())
+        }
+    }
+
+    /// Return the policy used to flush the log file
+    pub fn get_flush_policy(&self) -> FlushPolicy {
+        self.flush_policy.clone()
+    }
+
+    /// Define on which thread the messages are written
+    ///
+    /// By default, messages are written synchronously by the thread that sends them.
+    /// In WriteMode::Async, the messages are pushed to a bounded queue and written by a dedicated
+    /// thread, the `overflow_policy` defines what happens when the queue is full.
+    /// Use `exec_logger::log::flush` or `exec_logger::log::shutdown` to make sure every message is
+    /// written before the process exits.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{LoggerConfiguration, OverflowPolicy, WriteMode};
+    /// let mut config = LoggerConfiguration::default();
+    /// config
+    ///     .set_write_mode(WriteMode::Async {
+    ///         capacity: 1024,
+    ///         overflow_policy: OverflowPolicy::DropOldest,
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn set_write_mode(&mut self, write_mode: WriteMode) -> Result<(), ConfigError> {
+        if let WriteMode::Async { capacity: 0, .. } = write_mode {
+            eprintln!("Capacity of the log queue must be positive. Write mode is unchanged");
+            Err(ConfigError::InvalidValue(
+                "Capacity of the log queue must be positive".to_string(),
+            ))
+        } else {
+            self.write_mode = write_mode;
+            Ok(())
+        }
+    }
+
+    /// Return the mode used to write the messages
+    pub fn get_write_mode(&self) -> WriteMode {
+        self.write_mode.clone()
+    }
+
+    /// Define when the log file is rotated inside the execution folder
+    ///
+    /// By default, the log file is never rotated.
+    /// Rotation keeps the logs of a long running process in the same execution folder, without
+    /// the need to initialize the logger again
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{LoggerConfiguration, RotationPolicy};
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_rotation_policy(RotationPolicy::MaxBytes(10 * 1024 * 1024)).unwrap();
+    /// config.set_max_rotated_files(Some(5)); // execution_log.1.txt to execution_log.5.txt are kept
+    /// ```
+    pub fn set_rotation_policy(
+        &mut self,
+        rotation_policy: RotationPolicy,
+    ) -> Result<(), ConfigError> {
+        if rotation_policy == RotationPolicy::MaxBytes(0) {
+            eprintln!(
+                "Maximum size of the log file must be positive. Rotation policy is unchanged"
+            );
+            Err(ConfigError::InvalidValue(
+                "Maximum size of the log file must be positive".to_string(),
+            ))
+        } else {
+            self.rotation_policy = rotation_policy;
+            Ok(())
+        }
+    }
+
+    /// Return the policy used to rotate the log file
+    pub fn get_rotation_policy(&self) -> RotationPolicy {
+        self.rotation_policy.clone()
+    }
+
+    /// Define the number of rotated files kept in the execution folder, the oldest are deleted
+    ///
+    /// By default, there is no limit on rotated files
+    pub fn set_max_rotated_files(&mut self, max_rotated_files: Option<u32>) {
+        self.max_rotated_files = max_rotated_files;
+    }
+
+    pub fn get_max_rotated_files(&self) -> Option<u32> {
+        self.max_rotated_files
+    }
+
+    /// Add a destination for the log messages, besides the console and the log file
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - The destination, see the `Sink` trait
+    /// * `filter_level` - Lowest severity written to the sink, it replaces the filter level of the
+    ///   logger, the directives with a target still apply. None writes every message that passes the filter
+    ///   level of the logger
+    /// * `output_format` - Format of the messages written to the sink, None uses the output format
+    ///   of the logger
+    /// * `formatter` - Formatter of the text messages written to the sink, None uses the formatter
+    ///   or the message format of the logger
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::{LoggerConfiguration, OutputFormat};
+    /// # use exec_logger::log_level::LogLevel;
+    /// # use exec_logger::sink::ConsoleSink;
+    /// # use std::sync::Arc;
+    /// let mut config = LoggerConfiguration::default();
+    /// // Also print errors as JSON Lines
+    /// config.add_sink(
+    ///     Arc::new(ConsoleSink::default()),
+    ///     Some(LogLevel::Error),
+    ///     Some(OutputFormat::JsonLines),
+    ///     None,
+    /// );
+    /// ```
+    pub fn add_sink(
+        &mut self,
+        sink: Arc<dyn Sink>,
+        filter_level: Option<LogLevel>,
+        output_format: Option<OutputFormat>,
+        formatter: Option<Arc<dyn Formatter>>,
+    ) {
+        self.sinks
+            .push(SinkEntry::new(sink, filter_level, output_format, formatter));
+    }
+
+    /// Return the destinations added with add_sink
+    pub(crate) fn get_sinks(&self) -> &[SinkEntry] {
+        &self.sinks
+    }
+
+    /// Define the maximum disk usage of the executions in the log directory
+    ///
+    /// During housekeeping, the oldest executions are deleted until the executions fit in the limit.
+    /// Only folders created by the logger are counted and deleted, and the current execution
+    /// is never deleted.
+    /// The limit is also enforced each time the log file is rotated: the oldest executions are
+    /// deleted first, then the oldest rotated parts of the current execution. The log file being
+    /// written is never deleted, so without a rotation policy a long execution can still exceed
+    /// the limit on its own.
+    /// By default, there is no limit on disk usage
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::LoggerConfiguration;
+    /// let mut config = LoggerConfiguration::default();
+    /// config.set_max_total_bytes(Some(500 * 1024 * 1024)).unwrap(); // 500 MiB
+    /// ```
+    pub fn set_max_total_bytes(&mut self, max_total_bytes: Option<u64>) -> Result<(), ConfigError> {
+        if max_total_bytes == Some(0) {
+            eprintln!("Maximum disk usage must be positive. Maximum disk usage is unchanged");
+            Err(ConfigError::InvalidValue(
+                "Maximum disk usage must be positive".to_string(),
+            ))
+        } else {
+            self.max_total_bytes = max_total_bytes;
+            Ok(())
+        }
+    }
+
+    pub fn get_max_total_bytes(&self) -> Option<u64> {
+        self.max_total_bytes
+    }
+
+    /// Define which old executions are compressed during housekeeping instead of being kept as is
+    ///
+    /// By default, old executions are not compressed.
+    /// Only folders whose name was created by the logger are compressed
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::config::*;
+    /// let mut config = LoggerConfiguration::new("./logs".into(), "txt", Some(90), None, None);
+    /// // Keep the last 7 days as plain text and compressed archives up to 90 days
+    /// config.set_compression(Some(CompressionPolicy {
+    ///     format: CompressionFormat::Gzip,
+    ///     mode: CompressionMode::Archive,
+    ///     after_days: Some(7),
+    ///     after_executions: None,
+    /// }));
+    /// ```
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: Option<CompressionPolicy>) {
+        self.compression = compression;
+    }
+
+    /// Return the policy used to compress old executions
+    #[cfg(feature = "compression")]
+    pub fn get_compression(&self) -> Option<CompressionPolicy> {
+        self.compression.clone()
+    }
+
     pub fn get_system_name(&self) -> &str {
         &self.system_name
     }
@@ -222,6 +1114,21 @@ impl LoggerConfiguration {
         &self.user_name
     }
 
+    /// Used by LoggerConfigurationBuilder and the configuration files, the values must be checked
+    /// with check_storage first
+    pub(crate) fn set_storage(
+        &mut self,
+        log_dir: PathBuf,
+        file_extension: &str,
+        days_stored: Option<u32>,
+        executions_stored: Option<u32>,
+    ) {
+        self.log_dir = log_dir;
+        self.file_extension = file_extension.to_string();
+        self.days_stored = days_stored;
+        self.executions_stored = executions_stored;
+    }
+
     pub fn get_log_dir(&self) -> &Path {
         &self.log_dir
     }
@@ -239,6 +1146,66 @@ impl LoggerConfiguration {
     }
 }
 
+// Read the variable {prefix}_{name}, None if it is not set or empty
+fn env_var(prefix: &str, name: &str) -> Result<Option<String>, ConfigError> {
+    let variable = format!("{}_{}", prefix, name);
+    match std::env::var(&variable) {
+        Ok(value) if value.trim().is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(_)) => Err(ConfigError::InvalidValue(format!(
+            "{} is not valid unicode",
+            variable
+        ))),
+    }
+}
+
+// Parse the value of the variable {prefix}_{name} as a number, zero is rejected by check_storage
+fn parse_env_number(prefix: &str, name: &str, value: &str) -> Result<u32, ConfigError> {
+    value.trim().parse::<u32>().map_err(|_| {
+        ConfigError::InvalidValue(format!(
+            "{}_{} must be a positive number: {}",
+            prefix, name, value
+        ))
+    })
+}
+
+/// Check the values given to set_storage, returns every invalid value
+///
+/// The file extension must not be empty and the numbers of days and executions must be positive,
+/// with zero the housekeeping would delete every previous execution
+pub(crate) fn check_storage(
+    file_extension: &str,
+    days_stored: Option<u32>,
+    executions_stored: Option<u32>,
+) -> Result<(), Vec<ConfigError>> {
+    let mut errors = Vec::new();
+
+    if file_extension.trim().is_empty() {
+        errors.push(ConfigError::InvalidValue(
+            "File extension must not be empty".to_string(),
+        ));
+    }
+
+    if days_stored == Some(0) {
+        errors.push(ConfigError::InvalidValue(
+            "Days stored must be positive".to_string(),
+        ));
+    }
+
+    if executions_stored == Some(0) {
+        errors.push(ConfigError::InvalidValue(
+            "Executions stored must be positive".to_string(),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 impl Default for LoggerConfiguration {
     // Implementing the Default trait to provide default values
     fn default() -> Self {
@@ -251,3 +1218,50 @@ impl Default for LoggerConfiguration {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directive_matches() {
+        let directive = Directive {
+            target: "a::b".to_string(),
+            level: None,
+        };
+        assert!(directive.matches("a::b"));
+        assert!(directive.matches("a::b::c"));
+        assert!(!directive.matches("a::bc"));
+        assert!(!directive.matches("a"));
+        assert!(!directive.matches(""));
+
+        let directive = Directive {
+            target: String::new(),
+            level: Some(LogLevel::Warn),
+        };
+        assert!(directive.matches("a"));
+        assert!(directive.matches(""));
+    }
+
+    #[test]
+    fn test_get_directive() {
+        let mut config = LoggerConfiguration::default();
+        config
+            .set_directives("warn, a=info, a::b=trace, c=off")
+            .unwrap();
+        let level = |target: &str| config.get_target_directive(target).map(|x| x.level.clone());
+        assert_eq!(level("a::b::c"), Some(Some(LogLevel::Trace)));
+        assert_eq!(level("a::x"), Some(Some(LogLevel::Info)));
+        assert_eq!(level("c"), Some(None));
+        assert_eq!(level("other"), None);
+        assert_eq!(
+            config.get_default_directive().map(|x| x.level.clone()),
+            Some(Some(LogLevel::Warn))
+        );
+        assert_eq!(
+            config.get_directives()[1].to_string(),
+            "a=info",
+            "Directives are displayed as they are written"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+#[cfg(feature = "compression")]
+use super::config::CompressionPolicy;
+use super::config::{
+    check_storage, Color, ColorMode, ConfigError, ConsoleTarget, FlushPolicy, LoggerConfiguration,
+    OutputFormat, RotationPolicy, WriteMode,
+};
+use super::formatter::Formatter;
+use super::log_level::LogLevel;
+use super::sink::Sink;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Filter level or directives, applied by build in the order of the calls
+#[derive(Debug)]
+enum FilterStep {
+    Level(LogLevel),
+    Directives(String), // Validated by set_directives
+}
+
+/// Builds a LoggerConfiguration with named methods
+///
+/// Nothing is validated until `build`, which reports every invalid value at once.
+/// The log directory is not created by `build`, it is created by `Logger::new`: only its
+/// nearest existing folder is checked
+///
+/// # Example
+///
+/// ```rust
+/// # use exec_logger::config::LoggerConfiguration;
+/// # use exec_logger::log_level::LogLevel;
+/// let config = LoggerConfiguration::builder()
+///     .log_dir("logs")
+///     .file_extension("log")
+///     .days_stored(7)
+///     .filter_level(LogLevel::Info)
+///     .message_format("{TIMESTAMP} | {LEVEL} | {MESSAGE}")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct LoggerConfigurationBuilder {
+    config: LoggerConfiguration,       // Options that do not need validation
+    log_dir: PathBuf,                  // Its nearest existing folder must be writable
+    file_extension: String,            // Must not be empty
+    days_stored: Option<u32>,          // Must be positive
+    executions_stored: Option<u32>,    // Must be positive
+    message_format: Option<String>,    // Validated by set_message_format
+    timestamp_format: Option<String>,  // Validated by set_timestamp_format
+    filter_steps: Vec<FilterStep>,     // Filter level and directives in the order of the calls
+    flush_policy: Option<FlushPolicy>, // Validated by set_flush_policy
+    write_mode: Option<WriteMode>,     // Validated by set_write_mode
+    rotation_policy: Option<RotationPolicy>, // Validated by set_rotation_policy
+    max_total_bytes: Option<u64>,      // Validated by set_max_total_bytes
+}
+
+impl LoggerConfiguration {
+    /// Create a builder with the default values, see LoggerConfigurationBuilder
+    pub fn builder() -> LoggerConfigurationBuilder {
+        let config = LoggerConfiguration::default();
+        LoggerConfigurationBuilder {
+            log_dir: config.get_log_dir().to_path_buf(),
+            file_extension: config.get_file_extension().to_string(),
+            days_stored: None,
+            executions_stored: None,
+            message_format: None,
+            timestamp_format: None,
+            filter_steps: Vec::new(),
+            flush_policy: None,
+            write_mode: None,
+            rotation_policy: None,
+            max_total_bytes: None,
+            config,
+        }
+    }
+}
+
+// Check that the log directory can be created, without creating anything: the nearest existing
+// folder of the path must be a writable directory
+fn check_log_dir(log_dir: &Path) -> Result<(), String> {
+    let existing = log_dir
+        .ancestors()
+        .map(|x| {
+            if x.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                x
+            }
+        })
+        .find_map(|x| std::fs::metadata(x).ok().map(|metadata| (x, metadata)));
+
+    match existing {
+        Some((path, metadata)) if !metadata.is_dir() => {
+            Err(format!("{} is not a directory", path.display()))
+        }
+        Some((path, metadata)) if metadata.permissions().readonly() => {
+            Err(format!("{} is read only", path.display()))
+        }
+        Some(_) => Ok(()),
+        None => Err("no parent folder exists".to_string()),
+    }
+}
+
+impl LoggerConfigurationBuilder {
+    /// Root directory of the execution folders
+    pub fn log_dir<P: Into<PathBuf>>(mut self, log_dir: P) -> Self {
+        self.log_dir = log_dir.into();
+        self
+    }
+
+    /// Extension of the log files, without the dot
+    pub fn file_extension(mut self, file_extension: &str) -> Self {
+        self.file_extension = file_extension.to_string();
+        self
+    }
+
+    /// Number of days the executions are kept
+    pub fn days_stored(mut self, days_stored: u32) -> Self {
+        self.days_stored = Some(days_stored);
+        self
+    }
+
+    /// Number of executions kept
+    pub fn executions_stored(mut self, executions_stored: u32) -> Self {
+        self.executions_stored = Some(executions_stored);
+        self
+    }
+
+    /// See LoggerConfiguration::set_filter_level
+    pub fn filter_level(mut self, filter_level: LogLevel) -> Self {
+        self.filter_steps.push(FilterStep::Level(filter_level));
+        self
+    }
+
+    /// See LoggerConfiguration::set_directives
+    pub fn directives(mut self, directives: &str) -> Self {
+        self.filter_steps
+            .push(FilterStep::Directives(directives.to_string()));
+        self
+    }
+
+    /// See LoggerConfiguration::add_custom_level
+    pub fn custom_level(mut self, name: &str, severity: i32) -> Self {
+        self.config.add_custom_level(name, severity);
+        self
+    }
+
+    /// See LoggerConfiguration::set_console_level
+    pub fn console_level(mut self, console_level: LogLevel) -> Self {
+        self.config.set_console_level(Some(console_level));
+        self
+    }
+
+    /// See LoggerConfiguration::set_file_level
+    pub fn file_level(mut self, file_level: LogLevel) -> Self {
+        self.config.set_file_level(Some(file_level));
+        self
+    }
+
+    /// See LoggerConfiguration::set_console_target
+    pub fn console_target(mut self, console_target: ConsoleTarget) -> Self {
+        self.config.set_console_target(console_target);
+        self
+    }
+
+    /// See LoggerConfiguration::set_color_mode
+    pub fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.config.set_color_mode(color_mode);
+        self
+    }
+
+    /// See LoggerConfiguration::set_level_color
+    pub fn level_color(mut self, level: &LogLevel, color: Option<Color>) -> Self {
+        self.config.set_level_color(level, color);
+        self
+    }
+
+    /// See LoggerConfiguration::set_message_format
+    pub fn message_format(mut self, message_format: &str) -> Self {
+        self.message_format = Some(message_format.to_string());
+        self
+    }
+
+    /// See LoggerConfiguration::set_formatter
+    pub fn formatter(mut self, formatter: Box<dyn Formatter>) -> Self {
+        self.config.set_formatter(Some(formatter));
+        self
+    }
+
+    /// See LoggerConfiguration::set_timestamp_format
+    pub fn timestamp_format(mut self, timestamp_format: &str) -> Self {
+        self.timestamp_format = Some(timestamp_format.to_string());
+        self
+    }
+
+    /// See LoggerConfiguration::set_output_format
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.config.set_output_format(output_format);
+        self
+    }
+
+    /// See LoggerConfiguration::set_flush_policy
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = Some(flush_policy);
+        self
+    }
+
+    /// See LoggerConfiguration::set_write_mode
+    pub fn write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.write_mode = Some(write_mode);
+        self
+    }
+
+    /// See LoggerConfiguration::set_rotation_policy
+    pub fn rotation_policy(mut self, rotation_policy: RotationPolicy) -> Self {
+        self.rotation_policy = Some(rotation_policy);
+        self
+    }
+
+    /// See LoggerConfiguration::set_max_rotated_files
+    pub fn max_rotated_files(mut self, max_rotated_files: u32) -> Self {
+        self.config.set_max_rotated_files(Some(max_rotated_files));
+        self
+    }
+
+    /// See LoggerConfiguration::set_max_total_bytes
+    pub fn max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+        self.max_total_bytes = Some(max_total_bytes);
+        self
+    }
+
+    /// See LoggerConfiguration::set_compression
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, compression: CompressionPolicy) -> Self {
+        self.config.set_compression(Some(compression));
+        self
+    }
+
+    /// See LoggerConfiguration::add_sink
+    pub fn sink(
+        mut self,
+        sink: Arc<dyn Sink>,
+        filter_level: Option<LogLevel>,
+        output_format: Option<OutputFormat>,
+        formatter: Option<Arc<dyn Formatter>>,
+    ) -> Self {
+        self.config
+            .add_sink(sink, filter_level, output_format, formatter);
+        self
+    }
+
+    /// Validate every value and create the configuration
+    ///
+    /// Returns ConfigError::Multiple with every invalid value
+    pub fn build(self) -> Result<LoggerConfiguration, ConfigError> {
+        let mut config = self.config;
+        let mut errors = Vec::new();
+
+        if let Err(e) = check_log_dir(&self.log_dir) {
+            errors.push(ConfigError::InvalidValue(format!(
+                "Log directory {} is not writable: {}",
+                self.log_dir.display(),
+                e
+            )));
+        }
+
+        if let Err(e) = check_storage(
+            &self.file_extension,
+            self.days_stored,
+            self.executions_stored,
+        ) {
+            errors.extend(e);
+        }
+
+        config.set_storage(
+            self.log_dir,
+            &self.file_extension,
+            self.days_stored,
+            self.executions_stored,
+        );
+
+        // Applied in the order of the calls, so the result is the same as calling the setters
+        for step in self.filter_steps {
+            match step {
+                FilterStep::Level(filter_level) => config.set_filter_level(filter_level),
+                FilterStep::Directives(directives) => {
+                    if let Err(e) = config.set_directives(&directives) {
+                        errors.push(e);
+                    }
+                }
+            }
+        }
+
+        let results = [
+            self.message_format.map(|x| config.set_message_format(&x)),
+            self.timestamp_format
+                .map(|x| config.set_timestamp_format(&x)),
+            self.flush_policy.map(|x| config.set_flush_policy(x)),
+            self.write_mode.map(|x| config.set_write_mode(x)),
+            self.rotation_policy.map(|x| config.set_rotation_policy(x)),
+            self.max_total_bytes
+                .map(|x| config.set_max_total_bytes(Some(x))),
+        ];
+        errors.extend(results.into_iter().flatten().filter_map(Result::err));
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Multiple(errors))
+        }
+    }
+}
@@ -0,0 +1,481 @@
+#[cfg(feature = "compression")]
+use super::config::CompressionPolicy;
+use super::config::{
+    check_storage, Color, ColorMode, ConfigError, ConsoleTarget, FlushPolicy, LoggerConfiguration,
+    OutputFormat, OverflowPolicy, RotationPolicy, WriteMode,
+};
+use super::log_level::LogLevel;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Custom level declared in a configuration file
+#[derive(Debug, Deserialize, Serialize)]
+struct CustomLevel {
+    name: String,
+    severity: i32,
+}
+
+/// Color of a level in a configuration file, no color removes the color of the level
+#[derive(Debug, Deserialize, Serialize)]
+struct LevelColor {
+    level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<Color>,
+}
+
+/// ConsoleTarget in a configuration file, e.g. "stderr" or { split = "error" }
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FileConsoleTarget {
+    Stdout,
+    Stderr,
+    Split(String),
+    Disabled,
+}
+
+/// FlushPolicy in a configuration file, e.g. "every_line" or { interval_ms = 500 }
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FileFlushPolicy {
+    EveryLine,
+    IntervalMs(u64),
+    Severity(String),
+}
+
+/// OverflowPolicy in a configuration file, e.g. "drop_oldest" or { drop_below = "error" }
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FileOverflowPolicy {
+    Block,
+    DropNewest,
+    DropOldest,
+    DropBelow(String),
+}
+
+/// WriteMode in a configuration file, e.g. "sync" or
+/// { async = { capacity = 1024, overflow_policy = "block" } }
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FileWriteMode {
+    Sync,
+    Async {
+        capacity: usize,
+        overflow_policy: FileOverflowPolicy,
+    },
+}
+
+/// Representation of a LoggerConfiguration in a configuration file
+///
+/// Every value is optional, the missing values keep the default configuration.
+/// The values are applied with the setters of LoggerConfiguration, so they are validated the same way.
+/// The sinks added with add_sink and the formatter are code, they cannot be written to a file
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_extension: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    days_stored: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    executions_stored: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    custom_levels: Vec<CustomLevel>, // Declared before the levels, so they can be used by name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<String>, // Filter level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    directives: Option<String>, // See LoggerConfiguration::set_directives
+    #[serde(skip_serializing_if = "Option::is_none")]
+    console_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    console_target: Option<FileConsoleTarget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color_mode: Option<ColorMode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    level_colors: Vec<LevelColor>, // Only the colors that differ from the default colors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_format: Option<OutputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flush_policy: Option<FileFlushPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    write_mode: Option<FileWriteMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rotation_policy: Option<RotationPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_rotated_files: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_total_bytes: Option<u64>,
+    #[cfg(feature = "compression")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<CompressionPolicy>,
+}
+
+// Parse a level name of the file, the custom levels must already be declared
+fn parse_level(config: &LoggerConfiguration, name: &str) -> Result<LogLevel, ConfigError> {
+    config.parse_level(name).map_err(ConfigError::InvalidValue)
+}
+
+// Name of a level in the file, custom levels are written with the name they were declared with
+fn level_name(level: &LogLevel) -> String {
+    level.to_string().to_lowercase()
+}
+
+impl ConfigFile {
+    /// Read a TOML, YAML or JSON file, according to the extension
+    pub(crate) fn read(path: &Path) -> Result<ConfigFile, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(x) => x,
+            Err(e) => {
+                let details = format!("Could not read {}: {}", path.display(), e);
+                eprintln!("{}", details);
+                return Err(ConfigError::InvalidValue(details));
+            }
+        };
+
+        let extension = path.extension().and_then(|x| x.to_str()).unwrap_or("");
+        let result = match extension.to_ascii_lowercase().as_str() {
+            "toml" => toml::from_str::<ConfigFile>(&contents).map_err(|e| e.to_string()),
+            "json" => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+            "yaml" | "yml" => serde_yaml::from_str(&contents).map_err(|e| e.to_string()),
+            _ => Err(format!(
+                "Unknown configuration file extension: {}",
+                path.display()
+            )),
+        };
+
+        result.map_err(|e| {
+            eprintln!("Invalid configuration file {}: {}", path.display(), e);
+            ConfigError::InvalidFormat(e)
+        })
+    }
+
+    /// Set the values present in the file, the other values of the configuration are kept
+    pub(crate) fn apply(&self, config: &mut LoggerConfiguration) -> Result<(), ConfigError> {
+        let log_dir = match &self.log_dir {
+            Some(x) => x.clone(),
+            None => config.get_log_dir().to_path_buf(),
+        };
+        let file_extension = match &self.file_extension {
+            Some(x) => x.clone(),
+            None => config.get_file_extension().to_string(),
+        };
+        let days_stored = self.days_stored.or(config.get_days_stored());
+        let executions_stored = self.executions_stored.or(config.get_executions_stored());
+        check_storage(&file_extension, days_stored, executions_stored)
+            .map_err(ConfigError::from_errors)?;
+        config.set_storage(log_dir, &file_extension, days_stored, executions_stored);
+
+        for custom_level in &self.custom_levels {
+            config.add_custom_level(&custom_level.name, custom_level.severity);
+        }
+
+        if let Some(name) = &self.level {
+            let level = parse_level(config, name)?;
+            config.set_filter_level(level);
+        }
+        if let Some(directives) = &self.directives {
+            config.set_directives(directives)?;
+        }
+        if let Some(name) = &self.console_level {
+            let level = parse_level(config, name)?;
+            config.set_console_level(Some(level));
+        }
+        if let Some(name) = &self.file_level {
+            let level = parse_level(config, name)?;
+            config.set_file_level(Some(level));
+        }
+
+        if let Some(console_target) = &self.console_target {
+            let console_target = match console_target {
+                FileConsoleTarget::Stdout => ConsoleTarget::Stdout,
+                FileConsoleTarget::Stderr => ConsoleTarget::Stderr,
+                FileConsoleTarget::Split(name) => ConsoleTarget::Split(parse_level(config, name)?),
+                FileConsoleTarget::Disabled => ConsoleTarget::Disabled,
+            };
+            config.set_console_target(console_target);
+        }
+        if let Some(color_mode) = &self.color_mode {
+            config.set_color_mode(color_mode.clone());
+        }
+        for level_color in &self.level_colors {
+            let level = parse_level(config, &level_color.level)?;
+            config.set_level_color(&level, level_color.color);
+        }
+
+        if let Some(format) = &self.message_format {
+            config.set_message_format(format)?;
+        }
+        if let Some(format) = &self.timestamp_format {
+            config.set_timestamp_format(format)?;
+        }
+        if let Some(output_format) = &self.output_format {
+            config.set_output_format(output_format.clone());
+        }
+
+        if let Some(flush_policy) = &self.flush_policy {
+            let flush_policy = match flush_policy {
+                FileFlushPolicy::EveryLine => FlushPolicy::EveryLine,
+                FileFlushPolicy::IntervalMs(ms) => {
+                    FlushPolicy::Interval(Duration::from_millis(*ms))
+                }
+                FileFlushPolicy::Severity(name) => {
+                    FlushPolicy::Severity(parse_level(config, name)?)
+                }
+            };
+            config.set_flush_policy(flush_policy)?;
+        }
+        if let Some(write_mode) = &self.write_mode {
+            let write_mode = match write_mode {
+                FileWriteMode::Sync => WriteMode::Sync,
+                FileWriteMode::Async {
+                    capacity,
+                    overflow_policy,
+                } => WriteMode::Async {
+                    capacity: *capacity,
+                    overflow_policy: match overflow_policy {
+                        FileOverflowPolicy::Block => OverflowPolicy::Block,
+                        FileOverflowPolicy::DropNewest => OverflowPolicy::DropNewest,
+                        FileOverflowPolicy::DropOldest => OverflowPolicy::DropOldest,
+                        FileOverflowPolicy::DropBelow(name) => {
+                            OverflowPolicy::DropBelow(parse_level(config, name)?)
+                        }
+                    },
+                },
+            };
+            config.set_write_mode(write_mode)?;
+        }
+        if let Some(rotation_policy) = &self.rotation_policy {
+            config.set_rotation_policy(rotation_policy.clone())?;
+        }
+        if self.max_rotated_files.is_some() {
+            config.set_max_rotated_files(self.max_rotated_files);
+        }
+        if self.max_total_bytes.is_some() {
+            config.set_max_total_bytes(self.max_total_bytes)?;
+        }
+        #[cfg(feature = "compression")]
+        if self.compression.is_some() {
+            config.set_compression(self.compression.clone());
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<ConfigFile> for LoggerConfiguration {
+    type Error = ConfigError;
+
+    fn try_from(file: ConfigFile) -> Result<Self, Self::Error> {
+        let mut config = LoggerConfiguration::default();
+        file.apply(&mut config)?;
+        Ok(config)
+    }
+}
+
+impl From<LoggerConfiguration> for ConfigFile {
+    fn from(config: LoggerConfiguration) -> Self {
+        let defaults = LoggerConfiguration::default();
+        let directives = config.get_directives();
+
+        let console_target = match config.get_console_target() {
+            ConsoleTarget::Stdout => FileConsoleTarget::Stdout,
+            ConsoleTarget::Stderr => FileConsoleTarget::Stderr,
+            ConsoleTarget::Split(level) => FileConsoleTarget::Split(level_name(&level)),
+            ConsoleTarget::Disabled => FileConsoleTarget::Disabled,
+        };
+        let flush_policy = match config.get_flush_policy() {
+            FlushPolicy::EveryLine => FileFlushPolicy::EveryLine,
+            FlushPolicy::Interval(interval) => {
+                FileFlushPolicy::IntervalMs(interval.as_millis() as u64)
+            }
+            FlushPolicy::Severity(level) => FileFlushPolicy::Severity(level_name(&level)),
+        };
+        let write_mode = match config.get_write_mode() {
+            WriteMode::Sync => FileWriteMode::Sync,
+            WriteMode::Async {
+                capacity,
+                overflow_policy,
+            } => FileWriteMode::Async {
+                capacity,
+                overflow_policy: match overflow_policy {
+                    OverflowPolicy::Block => FileOverflowPolicy::Block,
+                    OverflowPolicy::DropNewest => FileOverflowPolicy::DropNewest,
+                    OverflowPolicy::DropOldest => FileOverflowPolicy::DropOldest,
+                    OverflowPolicy::DropBelow(level) => {
+                        FileOverflowPolicy::DropBelow(level_name(&level))
+                    }
+                },
+            },
+        };
+
+        // Every level used by the configuration, the custom ones must be declared in the file
+        let mut levels: Vec<LogLevel> = vec![
+            LogLevel::Error,
+            LogLevel::Warn,
+            LogLevel::Info,
+            LogLevel::Debug,
+            LogLevel::Trace,
+        ];
+        levels.extend(config.get_custom_levels());
+        levels.extend(config.get_filter_level());
+        levels.extend(config.get_console_level());
+        levels.extend(config.get_file_level());
+        levels.extend(directives.iter().filter_map(|x| x.level.clone()));
+        levels.extend(
+            config
+                .get_level_colors()
+                .into_iter()
+                .map(|(level, _)| level),
+        );
+        if let ConsoleTarget::Split(level) = config.get_console_target() {
+            levels.push(level);
+        }
+        if let FlushPolicy::Severity(level) = config.get_flush_policy() {
+            levels.push(level);
+        }
+        if let WriteMode::Async {
+            overflow_policy: OverflowPolicy::DropBelow(level),
+            ..
+        } = config.get_write_mode()
+        {
+            levels.push(level);
+        }
+
+        let mut seen: Vec<String> = Vec::new();
+        let mut custom_levels: Vec<CustomLevel> = Vec::new();
+        let mut level_colors: Vec<LevelColor> = Vec::new();
+        for level in levels {
+            let name = level.to_string();
+            if seen.iter().any(|x| x.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+            seen.push(name.clone());
+
+            if let LogLevel::Custom(severity, _) = &level {
+                custom_levels.push(CustomLevel {
+                    name: name.clone(),
+                    severity: *severity,
+                });
+            }
+
+            let color = config.get_level_color(&level);
+            if color != defaults.get_level_color(&level) {
+                level_colors.push(LevelColor {
+                    level: level_name(&level),
+                    color,
+                });
+            }
+        }
+
+        ConfigFile {
+            log_dir: Some(config.get_log_dir().to_path_buf()),
+            file_extension: Some(config.get_file_extension().to_string()),
+            days_stored: config.get_days_stored(),
+            executions_stored: config.get_executions_stored(),
+            custom_levels,
+            level: config.get_filter_level().as_ref().map(level_name),
+            directives: (!directives.is_empty()).then(|| {
+                directives
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            }),
+            console_level: config.get_console_level().as_ref().map(level_name),
+            file_level: config.get_file_level().as_ref().map(level_name),
+            console_target: Some(console_target),
+            color_mode: Some(config.get_color_mode()),
+            level_colors,
+            message_format: Some(config.get_message_format().to_string()),
+            timestamp_format: Some(config.get_timestamp_format().to_string()),
+            output_format: Some(config.get_output_format()),
+            flush_policy: Some(flush_policy),
+            write_mode: Some(write_mode),
+            rotation_policy: Some(config.get_rotation_policy()),
+            max_rotated_files: config.get_max_rotated_files(),
+            max_total_bytes: config.get_max_total_bytes(),
+            #[cfg(feature = "compression")]
+            compression: config.get_compression(),
+        }
+    }
+}
+
+impl LoggerConfiguration {
+    /// Load the configuration from a TOML, YAML or JSON file, according to the extension
+    ///
+    /// The values are validated by the setters, e.g. an invalid message format is an error.
+    /// Every option has a key named after its setter. The sinks added with add_sink and the
+    /// formatter cannot be written to a file, they are lost when a configuration is serialized.
+    /// The flush interval is written in milliseconds
+    ///
+    /// # Example
+    ///
+    /// ```toml
+    /// log_dir = "logs"
+    /// days_stored = 7
+    /// level = "audit"
+    /// directives = "my_crate::db=trace"
+    /// message_format = "{TIMESTAMP} | {LEVEL} | {MESSAGE}"
+    /// console_target = { split = "error" }
+    /// flush_policy = { interval_ms = 500 }
+    /// write_mode = { async = { capacity = 1024, overflow_policy = "drop_oldest" } }
+    /// rotation_policy = { max_bytes = 10485760 }
+    ///
+    /// [[custom_levels]]
+    /// name = "AUDIT"
+    /// severity = 45
+    ///
+    /// [[level_colors]]
+    /// level = "audit"
+    /// color = "magenta"
+    /// ```
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let file = ConfigFile::read(path)?;
+        LoggerConfiguration::try_from(file).map_err(|e| {
+            eprintln!("Invalid configuration file {}: {}", path.display(), e);
+            e
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_keeps_missing_values() {
+        let mut running = LoggerConfiguration::default();
+        running.set_console_target(ConsoleTarget::Disabled);
+        running.set_level_color(&LogLevel::Info, None);
+        running.set_file_level(Some(LogLevel::Debug));
+        let audit = running.add_custom_level("AUDIT", 45);
+
+        // Same steps as Logger::reload_file
+        let file: ConfigFile =
+            toml::from_str("level = \"audit\"\nconsole_level = \"warn\"").unwrap();
+        let mut config = running.clone();
+        file.apply(&mut config).unwrap();
+        running.apply_reloadable(&config);
+
+        assert_eq!(running.get_filter_level(), Some(audit));
+        assert_eq!(running.get_console_level(), Some(LogLevel::Warn));
+        assert_eq!(running.get_console_target(), ConsoleTarget::Disabled);
+        assert_eq!(running.get_level_color(&LogLevel::Info), None);
+        assert_eq!(running.get_file_level(), Some(LogLevel::Debug));
+
+        // The file can change them
+        let file: ConfigFile = toml::from_str("console_target = \"stderr\"").unwrap();
+        let mut config = running.clone();
+        file.apply(&mut config).unwrap();
+        running.apply_reloadable(&config);
+        assert_eq!(running.get_console_target(), ConsoleTarget::Stderr);
+    }
+}
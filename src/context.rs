@@ -0,0 +1,40 @@
+use super::record::{ToValue, Value};
+use std::cell::RefCell;
+
+thread_local! {
+    // Context fields of the current thread, from the outermost scope to the innermost
+    static CONTEXT: RefCell<Vec<(String, Value)>> = const { RefCell::new(Vec::new()) };
+}
+
+// Removes the fields of a scope when it ends, even if the closure panics
+struct ScopeGuard {
+    len: usize, // Number of fields before the scope
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|context| context.borrow_mut().truncate(self.len));
+    }
+}
+
+/// Run the closure with the fields added to the context of the current thread
+pub(crate) fn with_context<R, F: FnOnce() -> R>(fields: &[(&str, &dyn ToValue)], f: F) -> R {
+    let len = CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let len = context.len();
+        context.extend(
+            fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_value())),
+        );
+        len
+    });
+
+    let _guard = ScopeGuard { len };
+    f()
+}
+
+/// Return the context fields of the current thread
+pub(crate) fn current() -> Vec<(String, Value)> {
+    CONTEXT.with(|context| context.borrow().clone())
+}
@@ -0,0 +1,314 @@
+use super::config::{FlushPolicy, LoggerConfiguration, RotationPolicy};
+use super::log_level::LogLevel;
+use super::logger::Logger;
+use super::record::Record;
+use super::sink::Sink;
+use chrono::{Local, NaiveDate, Timelike};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct WriterState {
+    writer: Option<BufWriter<File>>, // None until the first message is written
+    last_flush: Instant,             // Used by FlushPolicy::Interval
+    size: u64,                       // Size of the current file, used by RotationPolicy::MaxBytes
+    period: (NaiveDate, u32),        // Day and hour the current file was opened
+}
+
+/// Keeps the log file open with a buffered writer shared by all the clones of a Logger
+#[derive(Debug)]
+pub(crate) struct FileWriter {
+    path: PathBuf,
+    flush_policy: FlushPolicy,
+    rotation_policy: RotationPolicy,
+    max_rotated_files: Option<u32>,
+    max_total_bytes: Option<u64>,
+    state: Mutex<WriterState>,
+    _flush_timer: Option<Sender<()>>, // Dropped to stop the thread of FlushPolicy::Interval
+}
+
+// Day and hour used to check if a time based rotation is necessary
+fn current_period(rotation_policy: &RotationPolicy) -> (NaiveDate, u32) {
+    let now = Local::now();
+    match rotation_policy {
+        RotationPolicy::Hourly => (now.date_naive(), now.hour()),
+        _ => (now.date_naive(), 0),
+    }
+}
+
+// Flush the log file every interval until the FileWriter is dropped
+fn start_flush_timer(file_writer: Weak<FileWriter>, interval: Duration) -> Option<Sender<()>> {
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let result = std::thread::Builder::new()
+        .name("exec_logger-flush".to_string())
+        .spawn(move || {
+            // Stops when the sender is dropped with the FileWriter
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if let Some(file_writer) = file_writer.upgrade() {
+                    file_writer.flush_if_due(interval);
+                }
+            }
+        });
+
+    match result {
+        Ok(_) => Some(stop),
+        Err(e) => {
+            eprintln!("Unable to start the flush thread of the log file: {}", e);
+            None
+        }
+    }
+}
+
+impl FileWriter {
+    /// Create the writer, with FlushPolicy::Interval a thread flushes the file every interval
+    pub(crate) fn new(path: PathBuf, config: &LoggerConfiguration) -> Arc<Self> {
+        let rotation_policy = config.get_rotation_policy();
+        let period = current_period(&rotation_policy);
+        let flush_policy = config.get_flush_policy();
+
+        Arc::new_cyclic(|file_writer| {
+            let flush_timer = match &flush_policy {
+                FlushPolicy::Interval(interval) => {
+                    start_flush_timer(file_writer.clone(), *interval)
+                }
+                _ => None,
+            };
+
+            FileWriter {
+                path,
+                flush_policy,
+                rotation_policy,
+                max_rotated_files: config.get_max_rotated_files(),
+                max_total_bytes: config.get_max_total_bytes(),
+                state: Mutex::new(WriterState {
+                    writer: None,
+                    last_flush: Instant::now(),
+                    size: 0,
+                    period,
+                }),
+                _flush_timer: flush_timer,
+            }
+        })
+    }
+
+    /// Write a formatted message to the log file, flushing according to the flush policy
+    pub(crate) fn write(&self, message: &str, level: &LogLevel) {
+        // A panic while holding the lock does not corrupt the writer, so keep logging
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if self.should_rotate(&state, message.len() as u64) {
+            self.rotate(&mut state);
+        }
+
+        if state.writer.is_none() {
+            // Open/create log file
+            match OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+            {
+                Ok(file) => {
+                    // The file may already exist if the logger was initialized again in the same second
+                    state.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                    state.period = current_period(&self.rotation_policy);
+                    state.writer = Some(BufWriter::new(file));
+                }
+                Err(e) => {
+                    eprintln!("Unable to open log file: {}", e);
+                    return;
+                }
+            }
+        }
+
+        let should_flush = match &self.flush_policy {
+            FlushPolicy::EveryLine => true,
+            FlushPolicy::Interval(interval) => state.last_flush.elapsed() >= *interval,
+            FlushPolicy::Severity(flush_level) => level >= flush_level,
+        };
+
+        // writer.unwrap() is safe, because the writer was opened above
+        let writer = state.writer.as_mut().unwrap();
+        if let Err(e) = writer.write_all(message.as_bytes()) {
+            eprintln!("Unable to write log message to log file: {}", e);
+        } else {
+            state.size += message.len() as u64;
+        }
+
+        if should_flush {
+            // writer.unwrap() is safe, because the writer was opened above
+            if let Err(e) = state.writer.as_mut().unwrap().flush() {
+                eprintln!("Unable to flush log file: {}", e);
+            }
+            state.last_flush = Instant::now();
+        }
+    }
+
+    /// Write the buffered messages to the log file
+    pub(crate) fn flush(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(writer) = state.writer.as_mut() {
+            if let Err(e) = writer.flush() {
+                eprintln!("Unable to flush log file: {}", e);
+            }
+        }
+        state.last_flush = Instant::now();
+    }
+
+    // Flush unless a message already did it during the interval
+    fn flush_if_due(&self, interval: Duration) {
+        let due = {
+            let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.last_flush.elapsed() >= interval
+        };
+        if due {
+            self.flush();
+        }
+    }
+
+    // Check if the current file must be rotated before writing a message of message_len bytes
+    fn should_rotate(&self, state: &WriterState, message_len: u64) -> bool {
+        if state.writer.is_none() {
+            // The size and period are only known after the file is opened
+            return false;
+        }
+
+        match &self.rotation_policy {
+            RotationPolicy::Never => false,
+            // A message bigger than the limit is still written to an empty file
+            RotationPolicy::MaxBytes(max_bytes) => {
+                state.size > 0 && state.size + message_len > *max_bytes
+            }
+            RotationPolicy::Hourly | RotationPolicy::Daily => {
+                current_period(&self.rotation_policy) != state.period
+            }
+        }
+    }
+
+    // Path of a rotated part, e.g. execution_log.1.txt for part 1 of execution_log.txt
+    fn part_path(&self, part: u32) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let file_name = match self.path.extension() {
+            Some(extension) => format!("{}.{}.{}", stem, part, extension.to_string_lossy()),
+            None => format!("{}.{}", stem, part),
+        };
+
+        self.path.with_file_name(file_name)
+    }
+
+    // Close the current file and shift the rotated parts: the current file becomes part 1,
+    // part 1 becomes part 2 and so on. Parts above max_rotated_files are deleted
+    fn rotate(&self, state: &mut WriterState) {
+        if let Some(mut writer) = state.writer.take() {
+            if let Err(e) = writer.flush() {
+                eprintln!("Unable to flush log file: {}", e);
+            }
+        }
+
+        let mut last_part = 0;
+        while self.part_path(last_part + 1).exists() {
+            last_part += 1;
+        }
+
+        for part in (1..=last_part).rev() {
+            let part_path = self.part_path(part);
+            let keep = match self.max_rotated_files {
+                Some(max_rotated_files) => part < max_rotated_files,
+                None => true,
+            };
+
+            let result = if keep {
+                std::fs::rename(&part_path, self.part_path(part + 1))
+            } else {
+                std::fs::remove_file(&part_path)
+            };
+
+            if let Err(e) = result {
+                eprintln!("Failed to rotate log file {:?}: {}", part_path, e);
+            }
+        }
+
+        let result = if self.max_rotated_files == Some(0) {
+            std::fs::remove_file(&self.path)
+        } else {
+            std::fs::rename(&self.path, self.part_path(1))
+        };
+
+        if let Err(e) = result {
+            eprintln!("Failed to rotate log file {:?}: {}", self.path, e);
+        }
+
+        self.limit_disk_usage();
+    }
+
+    // Keep the executions within max_total_bytes after a rotation: delete the oldest executions
+    // first, then the oldest rotated parts of the current execution
+    fn limit_disk_usage(&self) {
+        let max_total_bytes = match self.max_total_bytes {
+            Some(x) => x,
+            None => return,
+        };
+        let (current_dir, log_dir_root) = match self.path.parent() {
+            Some(current_dir) => match current_dir.parent() {
+                Some(log_dir_root) => (current_dir, log_dir_root),
+                None => return,
+            },
+            None => return,
+        };
+
+        let mut total_bytes =
+            match Logger::delete_exceeding_bytes(log_dir_root, current_dir, max_total_bytes) {
+                Ok(x) => x,
+                Err(e) => {
+                    eprintln!("Failed to delete old log folders: {}", e);
+                    return;
+                }
+            };
+
+        let mut last_part = 0;
+        while self.part_path(last_part + 1).exists() {
+            last_part += 1;
+        }
+
+        for part in (1..=last_part).rev() {
+            if total_bytes <= max_total_bytes {
+                break;
+            }
+
+            let part_path = self.part_path(part);
+            let size = Logger::disk_usage(&part_path);
+            if let Err(e) = std::fs::remove_file(&part_path) {
+                eprintln!("Failed to delete rotated log file {:?}: {}", part_path, e);
+            } else {
+                total_bytes = total_bytes.saturating_sub(size);
+            }
+        }
+    }
+}
+
+/// The log file of the execution is the built-in file sink
+impl Sink for FileWriter {
+    fn write(&self, record: &Record, message: &str) {
+        FileWriter::write(self, message, record.get_level());
+    }
+
+    fn flush(&self) {
+        FileWriter::flush(self);
+    }
+}
+
+impl Drop for FileWriter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
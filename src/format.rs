@@ -0,0 +1,185 @@
+use super::config::{LoggerConfiguration, OutputFormat};
+use super::formatter::{Formatter, FormatterRecord};
+use super::json;
+use super::record::{Record, Value};
+use super::template::{self, Keyword, Token};
+use chrono::{DateTime, Local};
+use std::borrow::Cow;
+
+/// Information of the execution of the logger that created the record
+#[derive(Clone, Debug)]
+pub(crate) struct Execution {
+    pub(crate) id: String,                  // Name of the execution folder
+    pub(crate) start_time: DateTime<Local>, // Time the logger was initialized
+}
+
+impl Execution {
+    // Seconds since the logger was initialized, with milliseconds
+    fn elapsed(&self, record: &Record) -> String {
+        let elapsed = *record.get_timestamp() - self.start_time;
+        format!("{:.3}", elapsed.num_milliseconds() as f64 / 1000.0)
+    }
+}
+
+/// Create the log message with the output format
+///
+/// The formatter, if given, replaces the formatter or the message format of the configuration
+pub(crate) fn format_record(
+    config: &LoggerConfiguration,
+    execution: &Execution,
+    record: &Record,
+    output_format: &OutputFormat,
+    formatter: Option<&dyn Formatter>,
+) -> String {
+    match output_format {
+        OutputFormat::Text => format_text(config, execution, record, formatter),
+        OutputFormat::JsonLines => format_json(config, execution, record),
+    }
+}
+
+/// Return the value of a keyword of the message format
+fn keyword_value<'a>(
+    keyword: Keyword,
+    config: &'a LoggerConfiguration,
+    execution: &'a Execution,
+    record: &'a Record,
+) -> Cow<'a, str> {
+    match keyword {
+        Keyword::Timestamp => {
+            let timestamp_format = config.get_timestamp_format();
+            Cow::Owned(record.get_timestamp().format(timestamp_format).to_string())
+        }
+        Keyword::ExeName => Cow::Borrowed(config.get_exe_name()),
+        Keyword::SystemName => Cow::Borrowed(config.get_system_name()),
+        Keyword::UserName => Cow::Borrowed(config.get_user_name()),
+        Keyword::Level => Cow::Owned(record.get_level().to_string()),
+        Keyword::Message => Cow::Borrowed(record.get_message()),
+        Keyword::Target => Cow::Borrowed(record.get_target()),
+        Keyword::Spans => Cow::Owned(record.spans_to_string()),
+        Keyword::Fields => Cow::Owned(record.fields_to_string()),
+        Keyword::Context => Cow::Owned(record.context_to_string()),
+        Keyword::ThreadName => Cow::Borrowed(record.get_thread_name().unwrap_or("<unnamed>")),
+        Keyword::ThreadId => Cow::Owned(record.get_thread_id().to_string()),
+        Keyword::Pid => Cow::Owned(std::process::id().to_string()),
+        Keyword::ExecutionId => Cow::Borrowed(&execution.id),
+        Keyword::Elapsed => Cow::Owned(execution.elapsed(record)),
+        Keyword::File => Cow::Borrowed(record.get_file().unwrap_or("")),
+        Keyword::Line => Cow::Owned(record.get_line().map(|x| x.to_string()).unwrap_or_default()),
+        Keyword::Module => Cow::Borrowed(record.get_module().unwrap_or("")),
+    }
+}
+
+/// Create the log message from the formatter or the format
+///
+/// The format is parsed by set_message_format, so the record is written in a single pass
+/// and the values (e.g. a message containing "{LEVEL}") are never substituted again
+fn format_text(
+    config: &LoggerConfiguration,
+    execution: &Execution,
+    record: &Record,
+    formatter: Option<&dyn Formatter>,
+) -> String {
+    let mut msg = String::with_capacity(record.get_message().len() + 128);
+
+    if let Some(formatter) = formatter.or(config.get_formatter()) {
+        formatter.format(
+            &FormatterRecord::new(record, config, &execution.id),
+            &mut msg,
+        );
+        if !msg.ends_with('\n') {
+            msg.push('\n');
+        }
+        return msg;
+    }
+
+    for token in config.get_message_template().tokens() {
+        match token {
+            Token::Literal(x) => msg.push_str(x),
+            Token::Keyword {
+                keyword,
+                width,
+                align,
+            } => {
+                let value = keyword_value(*keyword, config, execution, record);
+                template::push_aligned(&mut msg, &value, *width, *align);
+            }
+        }
+    }
+
+    if !msg.ends_with('\n') {
+        msg.push('\n');
+    }
+
+    msg
+}
+
+/// Write the pairs as a JSON object, nothing is written if there are no pairs
+fn write_object(msg: &mut String, name: &str, pairs: &[(String, Value)]) {
+    if pairs.is_empty() {
+        return;
+    }
+
+    msg.push(',');
+    json::write_string(msg, name);
+    msg.push_str(":{");
+    for (key, value) in pairs {
+        if !msg.ends_with('{') {
+            msg.push(',');
+        }
+        json::write_string(msg, key);
+        msg.push(':');
+        json::write_value(msg, value);
+    }
+    msg.push('}');
+}
+
+/// Create the log message as a JSON object in a single line
+fn format_json(config: &LoggerConfiguration, execution: &Execution, record: &Record) -> String {
+    let timestamp_format = config.get_timestamp_format();
+    let timestamp = record.get_timestamp().format(timestamp_format).to_string();
+    let level = record.get_level();
+
+    let mut msg = String::from("{");
+    json::write_pair(&mut msg, "timestamp", &timestamp);
+    json::write_pair(&mut msg, "level", &level.to_string());
+    msg.push_str(&format!(",\"severity\":{}", i32::from(level)));
+    json::write_pair(&mut msg, "exe", config.get_exe_name());
+    json::write_pair(&mut msg, "host", config.get_system_name());
+    json::write_pair(&mut msg, "user", config.get_user_name());
+    msg.push_str(&format!(",\"pid\":{}", std::process::id()));
+    json::write_pair(&mut msg, "execution_id", &execution.id);
+    if let Some(thread_name) = record.get_thread_name() {
+        json::write_pair(&mut msg, "thread", thread_name);
+    }
+    msg.push_str(&format!(",\"thread_id\":{}", record.get_thread_id()));
+    if !record.get_target().is_empty() {
+        json::write_pair(&mut msg, "target", record.get_target());
+    }
+    if let Some(file) = record.get_file() {
+        json::write_pair(&mut msg, "file", file);
+    }
+    if let Some(line) = record.get_line() {
+        msg.push_str(&format!(",\"line\":{}", line));
+    }
+    if let Some(module) = record.get_module() {
+        json::write_pair(&mut msg, "module", module);
+    }
+    json::write_pair(&mut msg, "message", record.get_message());
+
+    if !record.get_spans().is_empty() {
+        msg.push_str(",\"spans\":[");
+        for (i, span) in record.get_spans().iter().enumerate() {
+            if i > 0 {
+                msg.push(',');
+            }
+            json::write_string(&mut msg, span);
+        }
+        msg.push(']');
+    }
+
+    write_object(&mut msg, "fields", record.get_fields());
+    write_object(&mut msg, "context", record.get_context());
+
+    msg.push_str("}\n");
+    msg
+}
@@ -0,0 +1,121 @@
+use super::config::LoggerConfiguration;
+use super::log_level::LogLevel;
+use super::record::{Record, Value};
+use chrono::{DateTime, Local};
+use core::fmt;
+
+/// Renders the records written as text, in place of the message format
+///
+/// The formatter is used for the console, the log file and the sinks whose output format is
+/// `OutputFormat::Text`, the JSON Lines output is not changed. A newline is added if the
+/// formatter does not end the message with one. Set it with `LoggerConfiguration::set_formatter`,
+/// or for a single sink with `LoggerConfiguration::add_sink`
+///
+/// A formatter is code, so it is not written to configuration files and `log::reload` keeps the
+/// running one: while a formatter is set, the message format of a reloaded file is not used
+///
+/// # Example
+///
+/// ```rust
+/// use exec_logger::formatter::{Formatter, FormatterRecord};
+/// use std::fmt::Write;
+///
+/// // Writes the records as logfmt
+/// struct LogfmtFormatter;
+///
+/// impl Formatter for LogfmtFormatter {
+///     fn format(&self, record: &FormatterRecord, buffer: &mut String) {
+///         let _ = write!(
+///             buffer,
+///             "ts={} level={} msg={:?}",
+///             record.get_timestamp().to_rfc3339(),
+///             record.get_level(),
+///             record.get_message()
+///         );
+///         for (key, value) in record.get_fields() {
+///             let _ = write!(buffer, " {}={}", key, value);
+///         }
+///     }
+/// }
+/// ```
+pub trait Formatter: Send + Sync {
+    /// Write the record into the buffer
+    fn format(&self, record: &FormatterRecord, buffer: &mut String);
+}
+
+impl fmt::Debug for dyn Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Formatter")
+    }
+}
+
+/// Record given to a Formatter, with the information of the logger
+#[derive(Debug)]
+pub struct FormatterRecord<'a> {
+    record: &'a Record,
+    config: &'a LoggerConfiguration,
+    execution_id: &'a str,
+}
+
+impl<'a> FormatterRecord<'a> {
+    pub(crate) fn new(
+        record: &'a Record,
+        config: &'a LoggerConfiguration,
+        execution_id: &'a str,
+    ) -> Self {
+        FormatterRecord {
+            record,
+            config,
+            execution_id,
+        }
+    }
+
+    /// Return the record, e.g. for the target, the thread or the source location
+    pub fn get_record(&self) -> &Record {
+        self.record
+    }
+
+    pub fn get_level(&self) -> &LogLevel {
+        self.record.get_level()
+    }
+
+    pub fn get_message(&self) -> &str {
+        self.record.get_message()
+    }
+
+    pub fn get_timestamp(&self) -> &DateTime<Local> {
+        self.record.get_timestamp()
+    }
+
+    /// Return the format of the timestamp, see LoggerConfiguration::set_timestamp_format
+    pub fn get_timestamp_format(&self) -> &str {
+        self.config.get_timestamp_format()
+    }
+
+    pub fn get_exe_name(&self) -> &str {
+        self.config.get_exe_name()
+    }
+
+    pub fn get_system_name(&self) -> &str {
+        self.config.get_system_name()
+    }
+
+    pub fn get_user_name(&self) -> &str {
+        self.config.get_user_name()
+    }
+
+    /// Return the name of the execution folder
+    pub fn get_execution_id(&self) -> &str {
+        self.execution_id
+    }
+
+    /// Return the key/value fields of the record
+    pub fn get_fields(&self) -> &[(String, Value)] {
+        self.record.get_fields()
+    }
+
+    /// Return the context fields of the thread that sent the record
+    pub fn get_context(&self) -> &[(String, Value)] {
+        self.record.get_context()
+    }
+}
@@ -0,0 +1,93 @@
+use super::record::Value;
+use std::fmt::Write;
+
+/// Write a string as a JSON string, with quotes and escaped characters
+pub(crate) fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            c if c.is_control() => {
+                // Writing to a String never fails
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Write a "key":"value" pair of strings, preceded by a comma if it is not the first pair
+pub(crate) fn write_pair(out: &mut String, key: &str, value: &str) {
+    if !out.ends_with('{') {
+        out.push(',');
+    }
+    write_string(out, key);
+    out.push(':');
+    write_string(out, value);
+}
+
+/// Write a field value, numbers and booleans are written without quotes
+pub(crate) fn write_value(out: &mut String, value: &Value) {
+    // Writing to a String never fails
+    match value {
+        Value::Str(value) => write_string(out, value),
+        Value::I64(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Value::U64(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        // NaN and infinity are not valid JSON numbers
+        Value::F64(value) if !value.is_finite() => write_string(out, &value.to_string()),
+        Value::F64(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Value::Bool(value) => {
+            let _ = write!(out, "{}", value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_string() {
+        let mut out = String::new();
+        write_string(&mut out, "a \"b\" \\ c\nd\te\u{1}");
+        assert_eq!(out, r#""a \"b\" \\ c\nd\te\u0001""#);
+    }
+
+    #[test]
+    fn test_write_pair() {
+        let mut out = String::from("{");
+        write_pair(&mut out, "level", "INFO");
+        write_pair(&mut out, "message", "done");
+        assert_eq!(out, r#"{"level":"INFO","message":"done""#);
+    }
+
+    #[test]
+    fn test_write_value() {
+        let mut out = String::new();
+        for value in [
+            Value::Str("x".to_string()),
+            Value::I64(-1),
+            Value::U64(2),
+            Value::F64(0.5),
+            Value::F64(f64::NAN),
+            Value::Bool(true),
+        ] {
+            write_value(&mut out, &value);
+            out.push(' ');
+        }
+        assert_eq!(out, r#""x" -1 2 0.5 "NaN" true "#);
+    }
+}
@@ -1,6 +1,25 @@
 //! Exec Logger: A logging library focused on organizing logs per execution with automatic housekeeping
 
+mod async_writer;
+#[cfg(feature = "compression")]
+mod compression;
 pub mod config;
+mod config_builder;
+#[cfg(feature = "serde")]
+mod config_file;
+mod context;
+mod file_writer;
+mod format;
+pub mod formatter;
+mod json;
 pub mod log;
 pub mod log_level;
 pub mod logger;
+mod macros;
+pub mod record;
+pub mod sink;
+mod template;
+#[cfg(feature = "tracing")]
+pub mod tracing_layer;
+#[cfg(feature = "serde")]
+pub mod watcher;
@@ -1,11 +1,25 @@
 pub use self::functions::{
-    create_custom_level, custom, debug, error, get_log_file_path, info, initialize, trace, warn,
+    create_custom_level, custom, custom_kv, debug, debug_kv, enabled, error, error_kv, flush,
+    get_log_file_path, info, info_kv, initialize, install_log_facade, level, log_args, reload,
+    set_console_level, set_file_level, set_level, shutdown, trace, trace_kv, warn, warn_kv,
+    with_context,
 };
 
+#[cfg(feature = "tracing")]
+pub(crate) use self::functions::get_logger;
+#[cfg(feature = "serde")]
+pub(crate) use self::functions::reload_file;
+
 mod functions {
+    #[cfg(feature = "serde")]
+    use crate::config::ConfigError;
     use crate::config::LoggerConfiguration;
+    #[cfg(feature = "serde")]
+    use crate::config_file::ConfigFile;
+    use crate::context;
     use crate::log_level::LogLevel;
     use crate::logger::Logger;
+    use crate::record::ToValue;
 
     use arc_swap::{ArcSwap, Guard};
     use core::fmt;
@@ -13,11 +27,13 @@ mod functions {
     use std::path::PathBuf;
     use std::sync::Arc;
     static LOGGER: OnceCell<ArcSwap<Logger>> = OnceCell::new();
+    static LOG_FACADE: LogFacade = LogFacade;
 
     // Define custom error
     #[derive(Debug)]
     pub enum LoggerError {
         SetterError(String),
+        FacadeError(String),
     }
 
     impl fmt::Display for LoggerError {
@@ -28,6 +44,11 @@ mod functions {
                     "Logger error, could not set logger instance: {}",
                     details
                 ),
+                LoggerError::FacadeError(details) => write!(
+                    f,
+                    "Logger error, could not install the log crate facade: {}",
+                    details
+                ),
             }
         }
     }
@@ -35,11 +56,8 @@ mod functions {
     impl std::error::Error for LoggerError {}
 
     // Get internal logger from LOGGER
-    fn get_logger() -> Option<Guard<Arc<Logger>>> {
-        match LOGGER.get() {
-            Some(x) => Some(x.load()),
-            None => None,
-        }
+    pub(crate) fn get_logger() -> Option<Guard<Arc<Logger>>> {
+        LOGGER.get().map(|x| x.load())
     }
 
     // Set internal logger for LOGGER
@@ -50,24 +68,186 @@ mod functions {
                 Err(e) => return Err(LoggerError::SetterError(format!("{:?}", e))),
             }
         } else {
-            LOGGER.get().unwrap().store(Arc::new(logger));
+            // Stop the previous logger, its pending messages belong to the previous log file
+            let previous = LOGGER.get().unwrap().swap(Arc::new(logger));
+            previous.shutdown();
         }
         Ok(())
     }
 
     /// Initializes the logger with the given configuration
+    ///
+    /// The global logger lives until the process exits and is never dropped, so call `shutdown`
+    /// (or `flush`) before exiting when the configuration buffers messages: WriteMode::Async or a
+    /// FlushPolicy other than EveryLine. Otherwise the last messages are lost
     pub fn initialize(config: LoggerConfiguration) -> Result<(), anyhow::Error> {
         // There are other log implementations that allow for the log to be initialized only once
         // However this is not the case here, I want to be able to initialise the log more than
         // once if necessary to change the log file. Processes that execute for long periods might
         // occupy a lot of disk space and in this case it is possible to initialize the log again to
-        // generate a new log file.
+        // generate a new log file. To keep using the same execution folder, a RotationPolicy can be
+        // set in the configuration instead.
 
         let logger = Logger::new(config)?;
         set_logger(logger)?;
         Ok(())
     }
 
+    // Forwards the records of the `log` crate to whichever Logger is currently in LOGGER
+    struct LogFacade;
+
+    impl ::log::Log for LogFacade {
+        fn enabled(&self, metadata: &::log::Metadata) -> bool {
+            match get_logger() {
+                Some(logger) => ::log::Log::enabled(&**logger, metadata),
+                None => false,
+            }
+        }
+
+        fn log(&self, record: &::log::Record) {
+            if let Some(logger) = get_logger() {
+                ::log::Log::log(&**logger, record);
+            }
+        }
+
+        fn flush(&self) {
+            if let Some(logger) = get_logger() {
+                logger.flush();
+            }
+        }
+    }
+
+    /// Installs the logger as the global logger of the `log` crate
+    ///
+    /// Records emitted through the `log` macros (including the ones from dependencies) are written
+    /// to the current log file, honoring the filter level of the configuration.
+    /// The `log` crate only accepts one logger per process, so this function can only succeed once,
+    /// but it does not need to be called again after re-initializing the logger.
+    pub fn install_log_facade() -> Result<(), anyhow::Error> {
+        ::log::set_logger(&LOG_FACADE).map_err(|e| LoggerError::FacadeError(e.to_string()))?;
+        ::log::set_max_level(::log::LevelFilter::Trace);
+        Ok(())
+    }
+
+    /// Write every pending message to the log file
+    ///
+    /// In WriteMode::Async, waits until the writer thread has written every queued message.
+    /// Must be called before the process exits when the messages are buffered, see `initialize`
+    pub fn flush() {
+        if let Some(logger) = get_logger() {
+            logger.flush();
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Write every pending message and stop the writer thread
+    ///
+    /// Must be called before the process exits when using WriteMode::Async or a buffered
+    /// FlushPolicy, the global logger is never dropped. Messages sent after the shutdown are
+    /// written synchronously
+    pub fn shutdown() {
+        if let Some(logger) = get_logger() {
+            logger.shutdown();
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    // Replace the current logger by a modified copy, the writers are shared with the copy
+    fn update_logger<F: FnMut(&mut Logger)>(mut update: F) {
+        if let Some(current) = LOGGER.get() {
+            current.rcu(|logger| {
+                let mut logger = Logger::clone(logger);
+                update(&mut logger);
+                logger
+            });
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Apply a configuration to the current logger without creating a new execution folder
+    ///
+    /// Only the levels, directives, console output and formats are changed, see `Logger::reload`
+    pub fn reload(config: &LoggerConfiguration) {
+        update_logger(|logger| logger.reload(config));
+    }
+
+    // Apply the options present in a configuration file to the current logger, see ConfigWatcher
+    #[cfg(feature = "serde")]
+    pub(crate) fn reload_file(file: &ConfigFile) -> Result<(), ConfigError> {
+        let mut result = Ok(());
+        update_logger(|logger| result = logger.reload_file(file));
+        result
+    }
+
+    /// Change the filter level of the current logger without creating a new execution folder
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::log;
+    /// # use exec_logger::log_level::LogLevel;
+    /// log::set_level(Some(LogLevel::Trace)); // Investigate a problem without restarting the process
+    /// log::set_level(None); // Write every message
+    /// ```
+    ///
+    /// A directive without target is replaced by the filter level
+    pub fn set_level(filter_level: Option<LogLevel>) {
+        update_logger(|logger| logger.set_filter_level(filter_level.clone()));
+    }
+
+    /// Return the level of the messages that match no directive with a target: the directive
+    /// without target if there is one, otherwise the filter level of the current logger
+    ///
+    /// None if every message is written, if the directive without target is "off" or if the
+    /// logger is not initialized
+    pub fn level() -> Option<LogLevel> {
+        match get_logger() {
+            Some(logger) => logger.get_default_level(),
+            None => None,
+        }
+    }
+
+    /// Change the lowest severity printed to the console by the current logger
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::log;
+    /// # use exec_logger::log_level::LogLevel;
+    /// log::set_console_level(Some(LogLevel::Warn));
+    /// ```
+    pub fn set_console_level(console_level: Option<LogLevel>) {
+        update_logger(|logger| logger.set_console_level(console_level.clone()));
+    }
+
+    /// Change the lowest severity written to the log file by the current logger
+    pub fn set_file_level(file_level: Option<LogLevel>) {
+        update_logger(|logger| logger.set_file_level(file_level.clone()));
+    }
+
+    /// Check if a message with the level would be written by the current logger
+    pub fn enabled(level: &LogLevel) -> bool {
+        match get_logger() {
+            Some(logger) => logger.is_enabled(level),
+            None => false,
+        }
+    }
+
+    /// Logs a message from format arguments, it is only formatted if the level is enabled
+    ///
+    /// Used by the macros `info!`, `error!`, `warn!`, `debug!`, `trace!` and `custom!`
+    #[track_caller]
+    pub fn log_args(level: &LogLevel, module_path: &'static str, args: fmt::Arguments) {
+        if let Some(logger) = get_logger() {
+            logger.log_args(level, module_path, args);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
     /// Get the current log file path from the LOGGER
     pub fn get_log_file_path() -> Option<PathBuf> {
         if let Some(logger) = get_logger() {
@@ -79,6 +259,7 @@ mod functions {
     }
 
     /// Logs a INFO message
+    #[track_caller]
     pub fn info(message: &str) {
         if let Some(logger) = get_logger() {
             logger.info(message);
@@ -88,6 +269,7 @@ mod functions {
     }
 
     /// Logs a ERROR message
+    #[track_caller]
     pub fn error(message: &str) {
         if let Some(logger) = get_logger() {
             logger.error(message);
@@ -97,6 +279,7 @@ mod functions {
     }
 
     /// Logs a DEBUG message
+    #[track_caller]
     pub fn debug(message: &str) {
         if let Some(logger) = get_logger() {
             logger.debug(message);
@@ -106,6 +289,7 @@ mod functions {
     }
 
     /// Logs a TRACE message
+    #[track_caller]
     pub fn trace(message: &str) {
         if let Some(logger) = get_logger() {
             logger.trace(message);
@@ -115,6 +299,7 @@ mod functions {
     }
 
     /// Logs a WARN message
+    #[track_caller]
     pub fn warn(message: &str) {
         if let Some(logger) = get_logger() {
             logger.warn(message);
@@ -125,11 +310,11 @@ mod functions {
 
     /// Define a new LogLevel variant used for custom levels
     pub fn create_custom_level(name: &str, severity: i32) -> LogLevel {
-        let level = LogLevel::Custom(severity, name.to_string());
-        level
+        LogLevel::Custom(severity, name.to_string())
     }
 
     /// Logs a message with a custom log level
+    #[track_caller]
     pub fn custom(message: &str, level: &LogLevel) {
         if let Some(logger) = get_logger() {
             logger.custom(message, level);
@@ -137,4 +322,94 @@ mod functions {
             eprintln!("Logger not initialized")
         }
     }
+
+    /// Run the closure with context fields attached to every message sent inside it
+    ///
+    /// The context belongs to the current thread, scopes can be nested and the fields are
+    /// removed when the closure returns. The fields are shown by the {CONTEXT} keyword of the
+    /// message format and written as JSON fields by OutputFormat::JsonLines
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::log;
+    /// let job_id = 42;
+    /// log::with_context(&[("job", &job_id)], || {
+    ///     log::info("Job started"); // Has the field job=42
+    /// });
+    /// ```
+    pub fn with_context<R, F: FnOnce() -> R>(fields: &[(&str, &dyn ToValue)], f: F) -> R {
+        context::with_context(fields, f)
+    }
+
+    /// Logs a INFO message with key/value fields
+    ///
+    /// The fields are shown by the {FIELDS} keyword of the message format and written as
+    /// JSON fields by OutputFormat::JsonLines
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use exec_logger::log;
+    /// let order_id = "A-123";
+    /// log::info_kv("Order sent", &[("order_id", &order_id), ("retries", &3)]);
+    /// ```
+    #[track_caller]
+    pub fn info_kv(message: &str, fields: &[(&str, &dyn ToValue)]) {
+        if let Some(logger) = get_logger() {
+            logger.info_kv(message, fields);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Logs a ERROR message with key/value fields
+    #[track_caller]
+    pub fn error_kv(message: &str, fields: &[(&str, &dyn ToValue)]) {
+        if let Some(logger) = get_logger() {
+            logger.error_kv(message, fields);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Logs a DEBUG message with key/value fields
+    #[track_caller]
+    pub fn debug_kv(message: &str, fields: &[(&str, &dyn ToValue)]) {
+        if let Some(logger) = get_logger() {
+            logger.debug_kv(message, fields);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Logs a TRACE message with key/value fields
+    #[track_caller]
+    pub fn trace_kv(message: &str, fields: &[(&str, &dyn ToValue)]) {
+        if let Some(logger) = get_logger() {
+            logger.trace_kv(message, fields);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Logs a WARN message with key/value fields
+    #[track_caller]
+    pub fn warn_kv(message: &str, fields: &[(&str, &dyn ToValue)]) {
+        if let Some(logger) = get_logger() {
+            logger.warn_kv(message, fields);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
+
+    /// Logs a message with a custom log level and key/value fields
+    #[track_caller]
+    pub fn custom_kv(message: &str, level: &LogLevel, fields: &[(&str, &dyn ToValue)]) {
+        if let Some(logger) = get_logger() {
+            logger.custom_kv(message, level, fields);
+        } else {
+            eprintln!("Logger not initialized")
+        }
+    }
 }
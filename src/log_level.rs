@@ -1,5 +1,6 @@
 use core::fmt;
 use std::cmp::Ordering;
+use std::str::FromStr;
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum LogLevel {
@@ -24,6 +25,22 @@ impl fmt::Display for LogLevel {
     }
 }
 
+/// Parse the name of a default level, case insensitive (e.g. "warn", "WARN")
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!("Unknown log level: {}", name)),
+        }
+    }
+}
+
 impl From<&LogLevel> for i32 {
     fn from(log_level: &LogLevel) -> i32 {
         match log_level {
@@ -51,3 +68,28 @@ impl Ord for LogLevel {
         self_value.cmp(&other_value)
     }
 }
+
+impl From<log::Level> for LogLevel {
+    fn from(level: log::Level) -> LogLevel {
+        match level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warn,
+            log::Level::Info => LogLevel::Info,
+            log::Level::Debug => LogLevel::Debug,
+            log::Level::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl From<&tracing_core::Level> for LogLevel {
+    fn from(level: &tracing_core::Level) -> LogLevel {
+        match *level {
+            tracing_core::Level::ERROR => LogLevel::Error,
+            tracing_core::Level::WARN => LogLevel::Warn,
+            tracing_core::Level::INFO => LogLevel::Info,
+            tracing_core::Level::DEBUG => LogLevel::Debug,
+            tracing_core::Level::TRACE => LogLevel::Trace,
+        }
+    }
+}
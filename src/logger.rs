@@ -1,50 +1,213 @@
-use super::config::LoggerConfiguration;
+use super::async_writer::{AsyncWriter, Entry, SendResult};
+#[cfg(feature = "compression")]
+use super::compression;
+#[cfg(feature = "compression")]
+use super::config::CompressionPolicy;
+#[cfg(feature = "serde")]
+use super::config::ConfigError;
+use super::config::{ConsoleTarget, LoggerConfiguration, WriteMode};
+#[cfg(feature = "serde")]
+use super::config_file::ConfigFile;
+use super::file_writer::FileWriter;
+use super::format::Execution;
 use super::log_level::LogLevel;
+use super::record::{Record, ToValue};
+use super::sink::{ConsoleSink, Dispatcher, SinkEntry};
 use chrono::{Duration, Local, NaiveDateTime};
-use std::fs::OpenOptions;
-use std::io::Write;
+use core::fmt;
+#[cfg(feature = "compression")]
+use std::cmp::Reverse;
+use std::ffi::OsStr;
+use std::panic::Location;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+// Name of the execution folders, the names are sortable because they are in ISO 8601
+const EXECUTION_FOLDER_FORMAT: &str = "%Y-%m-%d %H_%M_%S";
+// Extensions of the archives created by CompressionMode::Archive
+const ARCHIVE_EXTENSIONS: [&str; 2] = [".tar.gz", ".tar.zst"];
+// File created in the folder of the execution to check that it is writable
+const WRITE_TEST_FILE: &str = ".exec_logger_write_test";
 
 #[derive(Clone, Debug)]
 pub struct Logger {
     config: LoggerConfiguration,
     log_file_path: PathBuf,
+    execution: Execution, // Execution id and start time, used by the message format
+    file_writer: Arc<FileWriter>, // Shared by clones, the file is flushed when the last one is dropped
+    dispatcher: Arc<Dispatcher>,  // Console, log file and sinks added to the configuration
+    async_writer: Option<Arc<AsyncWriter>>, // Writer thread used in WriteMode::Async
 }
 
 impl Logger {
     pub fn new(config: LoggerConfiguration) -> Result<Self, std::io::Error> {
-        let mut logger = Logger {
-            config,
-            log_file_path: PathBuf::new(),
+        let start_time = Local::now();
+        let current_datetime: NaiveDateTime = start_time.naive_local();
+        let folder_name = current_datetime.format(EXECUTION_FOLDER_FORMAT).to_string();
+        let log_dir = config.get_log_dir().join(PathBuf::from(&folder_name));
+        let execution = Execution {
+            id: folder_name,
+            start_time,
         };
 
-        let _ = &logger.delete_old_logs()?;
+        Logger::delete_old_logs(&config, &log_dir)?;
+
+        let log_file_path = Logger::create_current_log(&config, &log_dir)?;
+        let file_writer = FileWriter::new(log_file_path.clone(), &config);
 
-        let _ = &logger.create_current_log()?;
+        let dispatcher = Arc::new(Logger::create_dispatcher(&config, &execution, &file_writer));
+
+        let async_writer = match config.get_write_mode() {
+            WriteMode::Sync => None,
+            WriteMode::Async {
+                capacity,
+                overflow_policy,
+            } => Some(Arc::new(AsyncWriter::new(capacity, overflow_policy)?)),
+        };
+
+        let logger = Logger {
+            config,
+            log_file_path,
+            execution,
+            file_writer,
+            dispatcher,
+            async_writer,
+        };
 
         logger.info("Logger initialized");
 
         Ok(logger)
     }
 
+    /// Create the list of sinks: the console, the log file and the sinks of the configuration
+    fn create_dispatcher(
+        config: &LoggerConfiguration,
+        execution: &Execution,
+        file_writer: &Arc<FileWriter>,
+    ) -> Dispatcher {
+        let mut sinks = Vec::new();
+        let console_target = config.get_console_target();
+        if console_target != ConsoleTarget::Disabled {
+            sinks.push(SinkEntry::new(
+                Arc::new(
+                    ConsoleSink::new(console_target)
+                        .with_colors(&config.get_color_mode(), config.get_level_colors()),
+                ),
+                config.get_console_level(),
+                None,
+                None,
+            ));
+        }
+        sinks.push(SinkEntry::new(
+            file_writer.clone(),
+            config.get_file_level(),
+            None,
+            None,
+        ));
+        sinks.extend(config.get_sinks().iter().cloned());
+
+        Dispatcher::new(config.clone(), execution.clone(), sinks)
+    }
+
+    // Create the sinks again after a change of the configuration
+    fn update_dispatcher(&mut self) {
+        self.dispatcher = Arc::new(Logger::create_dispatcher(
+            &self.config,
+            &self.execution,
+            &self.file_writer,
+        ));
+    }
+
+    /// Change the filter level, None writes every message. The messages keep being written to
+    /// the same log file
+    ///
+    /// A directive without target is replaced by the filter level
+    pub fn set_filter_level(&mut self, filter_level: Option<LogLevel>) {
+        match filter_level {
+            Some(filter_level) => self.config.set_filter_level(filter_level),
+            None => self.config.clear_filter_level(),
+        }
+        self.update_dispatcher();
+    }
+
+    /// Return the filter level
+    pub fn get_filter_level(&self) -> Option<LogLevel> {
+        self.config.get_filter_level()
+    }
+
+    /// Return the level of the sinks without their own level, for the messages that match no
+    /// directive with a target: the level of the directive without target if there is one,
+    /// otherwise the filter level
+    ///
+    /// None if every message is written, or if the directive without target is "off"
+    pub fn get_default_level(&self) -> Option<LogLevel> {
+        match self.config.get_default_directive() {
+            Some(directive) => directive.level.clone(),
+            None => self.config.get_filter_level(),
+        }
+    }
+
+    /// Change the lowest severity printed to the console
+    ///
+    /// The messages already queued in WriteMode::Async keep the previous level
+    pub fn set_console_level(&mut self, console_level: Option<LogLevel>) {
+        self.config.set_console_level(console_level);
+        self.update_dispatcher();
+    }
+
+    /// Change the lowest severity written to the log file
+    ///
+    /// The messages already queued in WriteMode::Async keep the previous level
+    pub fn set_file_level(&mut self, file_level: Option<LogLevel>) {
+        self.config.set_file_level(file_level);
+        self.update_dispatcher();
+    }
+
+    /// Apply the levels, directives, console output and formats of a configuration, the
+    /// messages keep being written to the same log file
+    ///
+    /// The log directory, the housekeeping, the write mode, the flush and rotation policies,
+    /// the sinks added with add_sink and the formatter are not changed
+    pub fn reload(&mut self, config: &LoggerConfiguration) {
+        self.config.apply_reloadable(config);
+        self.update_dispatcher();
+    }
+
+    /// Reload only the options present in a configuration file, the other options keep their
+    /// running value. Nothing changes if a value of the file is invalid
+    #[cfg(feature = "serde")]
+    pub(crate) fn reload_file(&mut self, file: &ConfigFile) -> Result<(), ConfigError> {
+        let mut config = self.config.clone();
+        file.apply(&mut config)?;
+        self.reload(&config);
+        Ok(())
+    }
+
     /// Get log file path
     pub fn get_log_file_path(&self) -> PathBuf {
         self.log_file_path.clone()
     }
 
-    /// List folders in a path
-    fn list_folders(directory_path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    /// List folders and compressed execution archives in a path
+    /// The folder of the current execution is never listed, so it is never deleted or compressed
+    /// (it may already exist if the logger is initialized more than once in the same second)
+    fn list_folders(
+        directory_path: &Path,
+        current_dir: &Path,
+    ) -> Result<Vec<PathBuf>, std::io::Error> {
         let mut folders = Vec::new();
 
         // Read the directory entries using std::fs::read_dir
-        if let Ok(entries) = std::fs::read_dir(&directory_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        folders.push(path);
-                    }
+        if let Ok(entries) = std::fs::read_dir(directory_path) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path == current_dir {
+                    continue;
+                }
+
+                if path.is_dir() || Logger::is_archive(&path) {
+                    folders.push(path);
                 }
             }
         }
@@ -52,49 +215,70 @@ impl Logger {
         Ok(folders)
     }
 
+    /// Check if the path is a file with the extension of an archive created by the compression
+    fn is_archive(path: &Path) -> bool {
+        let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+        path.is_file() && ARCHIVE_EXTENSIONS.iter().any(|x| file_name.ends_with(x))
+    }
+
+    /// Get the datetime of an execution from the name of its folder or archive
+    ///
+    /// Returns None if the name was not created by the logger
+    fn parse_execution_datetime(path: &Path) -> Option<NaiveDateTime> {
+        let mut folder_name = path.file_name()?.to_str()?;
+
+        if Logger::is_archive(path) {
+            for extension in ARCHIVE_EXTENSIONS {
+                folder_name = folder_name.strip_suffix(extension).unwrap_or(folder_name);
+            }
+        }
+
+        NaiveDateTime::parse_from_str(folder_name, EXECUTION_FOLDER_FORMAT).ok()
+    }
+
+    /// Delete the folder or the archive of an execution
+    fn remove_execution(path: &Path) -> Result<(), std::io::Error> {
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        }
+    }
+
     /// Delete old logs according to the configuration file
     /// Old folders are deleted following 2 conditions in the LoggerConfiguration
     /// 1) Logs older than X days (days_stored)
     /// 2) Oldest logs exceeding the max number of logs (executions_stored)
-    fn delete_old_logs(&self) -> Result<(), std::io::Error> {
-        let log_dir_root = self.config.get_log_dir();
-        let days_stored = self.config.get_days_stored();
-        let executions_stored = self.config.get_executions_stored();
+    ///
+    /// Then the remaining old folders are compressed according to the CompressionPolicy
+    /// and the oldest folders are deleted until the logs fit in max_total_bytes
+    fn delete_old_logs(
+        config: &LoggerConfiguration,
+        current_dir: &Path,
+    ) -> Result<(), std::io::Error> {
+        let log_dir_root = config.get_log_dir();
+        let days_stored = config.get_days_stored();
+        let executions_stored = config.get_executions_stored();
 
         // 1) Delete based on date
         if let Some(days_stored) = days_stored {
-            let log_folders = Logger::list_folders(log_dir_root)?;
+            let log_folders = Logger::list_folders(log_dir_root, current_dir)?;
             let limit_datetime =
                 Local::now().naive_local() - Duration::days(i64::from(days_stored));
 
             for folder in log_folders {
-                let folder_name = folder.file_name();
-                if folder_name.is_none() {
-                    continue;
-                }
-
-                // folder_name.unwrap() is safe, because folder_name will not be None
-                let folder_name = folder_name.unwrap().to_str();
-                if folder_name.is_none() {
-                    continue;
-                }
-
-                let folder_name = folder_name.unwrap();
-
-                let datetime = NaiveDateTime::parse_from_str(folder_name, "%Y-%m-%d %H_%M_%S");
-                if datetime.is_err() {
-                    // If it is not possible to get a datetime from the file name, then the name was not created by the logger and must
-                    // not be deleted by the logger. It should be manually deleted
-                    continue;
-                }
-
-                let datetime = datetime.unwrap();
+                // If it is not possible to get a datetime from the file name, then the name was not created by the logger and must
+                // not be deleted by the logger. It should be manually deleted
+                let datetime = match Logger::parse_execution_datetime(&folder) {
+                    Some(x) => x,
+                    None => continue,
+                };
 
                 // I don't want to raise an error if it is not possible to delete the folder, because this may happen if
                 // the program is being executed with a different permission from a previous execution
                 // In this case, old logs need to be manually deleted
                 if datetime < limit_datetime {
-                    if let Err(e) = std::fs::remove_dir_all(&folder) {
+                    if let Err(e) = Logger::remove_execution(&folder) {
                         eprintln!("Failed to delete old log folder {:?}: {}", folder, e);
                     }
                 }
@@ -108,7 +292,7 @@ impl Logger {
 
         // 2) Delete based on number of logs
         if let Some(executions_stored) = executions_stored {
-            let mut log_folders = Logger::list_folders(log_dir_root)?;
+            let mut log_folders = Logger::list_folders(log_dir_root, current_dir)?;
 
             // If the executions to store is X, we must delete X - 1 (remove 1 to accomodate the current execution)
             let mut num_delete = log_folders.len() as i64 - (executions_stored as i64 - 1);
@@ -118,28 +302,14 @@ impl Logger {
                 log_folders.sort_by(|a, b| a.to_string_lossy().cmp(&b.to_string_lossy()));
 
                 for folder in log_folders {
-                    let folder_name = folder.file_name();
-                    if folder_name.is_none() {
-                        continue;
-                    }
-
-                    // folder_name.unwrap() is safe, because folder_name will not be None
-                    let folder_name = folder_name.unwrap().to_str();
-                    if folder_name.is_none() {
-                        continue;
-                    }
-
-                    let folder_name = folder_name.unwrap();
-
-                    let datetime = NaiveDateTime::parse_from_str(folder_name, "%Y-%m-%d %H_%M_%S");
-                    if datetime.is_err() {
+                    if Logger::parse_execution_datetime(&folder).is_none() {
                         // If it is not possible to get a datetime from the file name, then the name was not created by the logger and must
                         // not be deleted by the logger. It should be manually deleted
                         continue;
                     }
 
                     // try to delete the folder
-                    if let Err(e) = std::fs::remove_dir_all(&folder) {
+                    if let Err(e) = Logger::remove_execution(&folder) {
                         eprintln!("Failed to delete old log folder {:?}: {}", folder, e);
                     } else {
                         num_delete -= 1;
@@ -159,126 +329,400 @@ impl Logger {
             }
         }
 
+        // 3) Compress the remaining old logs
+        #[cfg(feature = "compression")]
+        if let Some(compression) = config.get_compression() {
+            Logger::compress_old_logs(log_dir_root, current_dir, &compression)?;
+        }
+
+        // 4) Delete based on disk usage
+        if let Some(max_total_bytes) = config.get_max_total_bytes() {
+            Logger::delete_exceeding_bytes(log_dir_root, current_dir, max_total_bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Size in bytes of a file or of all the files in a folder
+    ///
+    /// Symbolic links are not followed, so a linked file is not counted twice and a link to a
+    /// parent folder does not loop
+    pub(crate) fn disk_usage(path: &Path) -> u64 {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(x) => x,
+            Err(_) => return 0,
+        };
+
+        if metadata.file_type().is_symlink() {
+            0
+        } else if metadata.is_dir() {
+            match std::fs::read_dir(path) {
+                Ok(entries) => entries
+                    .flatten()
+                    .map(|entry| Logger::disk_usage(&entry.path()))
+                    .sum(),
+                Err(_) => 0,
+            }
+        } else {
+            metadata.len()
+        }
+    }
+
+    /// Delete the oldest executions until the executions created by the logger (the current
+    /// one included) fit in max_total_bytes. Folders not created by the logger are not counted
+    ///
+    /// Returns the disk usage of the remaining executions
+    pub(crate) fn delete_exceeding_bytes(
+        log_dir_root: &Path,
+        current_dir: &Path,
+        max_total_bytes: u64,
+    ) -> Result<u64, std::io::Error> {
+        let mut executions: Vec<(PathBuf, NaiveDateTime, u64)> =
+            Logger::list_folders(log_dir_root, current_dir)?
+                .into_iter()
+                .filter_map(|folder| {
+                    let datetime = Logger::parse_execution_datetime(&folder)?;
+                    let size = Logger::disk_usage(&folder);
+                    Some((folder, datetime, size))
+                })
+                .collect();
+
+        let mut total_bytes: u64 = Logger::disk_usage(current_dir)
+            + executions.iter().map(|execution| execution.2).sum::<u64>();
+
+        // Oldest first
+        executions.sort_by_key(|execution| execution.1);
+
+        for (folder, _, size) in executions {
+            if total_bytes <= max_total_bytes {
+                break;
+            }
+
+            if let Err(e) = Logger::remove_execution(&folder) {
+                eprintln!("Failed to delete old log folder {:?}: {}", folder, e);
+            } else {
+                total_bytes = total_bytes.saturating_sub(size);
+            }
+        }
+
+        Ok(total_bytes)
+    }
+
+    /// Compress the executions older than after_days or exceeding after_executions
+    #[cfg(feature = "compression")]
+    fn compress_old_logs(
+        log_dir_root: &Path,
+        current_dir: &Path,
+        compression: &CompressionPolicy,
+    ) -> Result<(), std::io::Error> {
+        // Archives are already compressed, only folders created by the logger are considered
+        let mut executions: Vec<(PathBuf, NaiveDateTime)> =
+            Logger::list_folders(log_dir_root, current_dir)?
+                .into_iter()
+                .filter(|folder| folder.is_dir())
+                .filter_map(|folder| {
+                    Logger::parse_execution_datetime(&folder).map(|datetime| (folder, datetime))
+                })
+                .collect();
+
+        // Newest first
+        executions.sort_by_key(|execution| Reverse(execution.1));
+
+        let limit_datetime = compression
+            .after_days
+            .map(|days| Local::now().naive_local() - Duration::days(i64::from(days)));
+
+        for (index, (folder, datetime)) in executions.iter().enumerate() {
+            let too_old = limit_datetime.is_some_and(|limit| *datetime < limit);
+            // The current execution is not created yet, so it takes one of the after_executions
+            let too_many = compression
+                .after_executions
+                .is_some_and(|after| index as i64 >= after as i64 - 1);
+
+            if too_old || too_many {
+                // Same as deletion, a failure must not prevent the logger from starting
+                if let Err(e) = compression::compress_execution(folder, compression) {
+                    eprintln!("Failed to compress old log folder {:?}: {}", folder, e);
+                }
+            }
+        }
+
         Ok(())
     }
 
-    /// Create current log file
-    fn create_current_log(&mut self) -> Result<(), std::io::Error> {
-        let current_datetime: NaiveDateTime = Local::now().naive_local();
-        let folder_name = current_datetime.format("%Y-%m-%d %H_%M_%S").to_string();
-        let log_dir = self.config.get_log_dir().join(PathBuf::from(folder_name));
-        std::fs::create_dir_all(&log_dir)?;
+    /// Create current log folder and return the path of the log file
+    ///
+    /// The log file is opened when the first message is written, so the folder is checked here
+    /// to report a log directory that is not writable
+    fn create_current_log(
+        config: &LoggerConfiguration,
+        log_dir: &Path,
+    ) -> Result<PathBuf, std::io::Error> {
+        std::fs::create_dir_all(log_dir)?;
+
+        let test_file = log_dir.join(WRITE_TEST_FILE);
+        std::fs::File::create(&test_file)
+            .and_then(|_| std::fs::remove_file(&test_file))
+            .map_err(|e| {
+                std::io::Error::new(
+                    e.kind(),
+                    format!("Log directory {} is not writable: {}", log_dir.display(), e),
+                )
+            })?;
 
-        let file_extension = self.config.get_file_extension();
+        let file_extension = config.get_file_extension();
         let file_name = format!("execution_log.{}", file_extension);
         let log_file_path = log_dir.join(PathBuf::from(file_name));
 
-        self.log_file_path = log_file_path;
-        Ok(())
+        Ok(log_file_path)
     }
 
-    /// Create the log message from the format
-    fn format_message(&self, message: &str, level: &LogLevel) -> String {
-        // Technically, using a HashMap could be cleaner instead of using many contains
-        // but the idea is to evaluate the parts of the message only if necessary
-        let mut msg = self.config.get_message_format().to_string();
+    /// Check if a message with the given level passes the filter level
+    pub fn is_enabled(&self, level: &LogLevel) -> bool {
+        self.is_enabled_for(level, "")
+    }
 
-        if msg.contains("{TIMESTAMP}") {
-            // Gets the time only if necessary
-            let timestamp_format = self.config.get_timestamp_format();
-            let now = Local::now().format(timestamp_format);
-            msg = msg.replace("{TIMESTAMP}", &now.to_string());
+    /// Check if a message with the given level and target is written by at least one sink
+    ///
+    /// The directive with a target that matches it decides if there is one. Otherwise the message
+    /// must pass the level of a sink, or the default level for the sinks without their own level
+    /// (see get_default_level)
+    pub fn is_enabled_for(&self, level: &LogLevel, target: &str) -> bool {
+        if let Some(directive) = self.config.get_target_directive(target) {
+            return match &directive.level {
+                Some(filter_level) => level >= filter_level,
+                None => false,
+            };
         }
 
-        if msg.contains("{EXE_NAME}") {
-            msg = msg.replace("{EXE_NAME}", self.config.get_exe_name());
-        }
+        self.dispatcher.is_enabled(level)
+    }
 
-        if msg.contains("{SYSTEM_NAME}") {
-            msg = msg.replace("{SYSTEM_NAME}", self.config.get_system_name());
-        }
+    /// Write the log message to the console and to the log file
+    ///
+    /// The record has an empty target, see LoggerConfiguration::set_directives
+    #[track_caller]
+    fn log(&self, message: &str, level: &LogLevel) {
+        self.log_kv(message, level, &[]);
+    }
 
-        if msg.contains("{USER_NAME}") {
-            msg = msg.replace("{USER_NAME}", self.config.get_user_name());
-        }
+    /// Write the log message from format arguments, formatting it only if the level is enabled
+    ///
+    /// The module path is the target of the record, the location is the caller
+    #[track_caller]
+    pub fn log_args(&self, level: &LogLevel, module_path: &'static str, args: fmt::Arguments) {
+        let location = Location::caller();
+        self.log_with(
+            level,
+            module_path,
+            Some(module_path),
+            Some((location.file(), location.line())),
+            args,
+            &[],
+        );
+    }
 
-        if msg.contains("{LEVEL}") {
-            msg = msg.replace("{LEVEL}", &format!("{:<7}", level.to_string()));
-        }
+    /// Write the log message with key/value fields
+    ///
+    /// The record has an empty target, see LoggerConfiguration::set_directives
+    #[track_caller]
+    fn log_kv(&self, message: &str, level: &LogLevel, fields: &[(&str, &dyn ToValue)]) {
+        let location = Location::caller();
+        self.log_with(
+            level,
+            "",
+            None,
+            Some((location.file(), location.line())),
+            format_args!("{}", message),
+            fields,
+        );
+    }
 
-        if msg.contains("{MESSAGE}") {
-            msg = msg.replace("{MESSAGE}", message);
+    // Every message sent to the logger ends here. The message and the fields are only
+    // converted if the record passes the filters
+    fn log_with(
+        &self,
+        level: &LogLevel,
+        target: &str,
+        module: Option<&'static str>,
+        location: Option<(&'static str, u32)>,
+        args: fmt::Arguments,
+        fields: &[(&str, &dyn ToValue)],
+    ) {
+        if !self.is_enabled_for(level, target) {
+            return;
         }
 
-        if !msg.ends_with('\n') {
-            msg.push('\n');
+        // Messages without arguments do not need to be formatted
+        let mut record = match args.as_str() {
+            Some(message) => Record::new(level.clone(), message),
+            None => Record::new(level.clone(), &args.to_string()),
+        }
+        .with_target(target)
+        .with_current_thread();
+
+        if !fields.is_empty() {
+            record = record.with_fields(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_value()))
+                    .collect(),
+            );
+        }
+        if let Some(module) = module {
+            record = record.with_module(module);
+        }
+        if let Some((file, line)) = location {
+            record = record.with_location(file, line);
         }
 
-        msg
+        self.write_record(&record);
     }
 
-    /// Write the log message to stdout and to the log file
-    fn log(&self, message: &str, level: &LogLevel) {
-        // Check if the message level has severity higher than the minimum
-        match self.config.get_filter_level() {
-            Some(filter_level) => {
-                if *level < filter_level {
-                    return; // return from the function without doing anything
-                }
-            }
-            None => (),
+    /// Write a record to every sink
+    ///
+    /// The thread and the context are the ones of the record, see Record::with_current_thread
+    pub fn log_record(&self, record: &Record) {
+        if !self.is_enabled_for(record.get_level(), record.get_target()) {
+            return;
         }
 
-        let message_formatted = &self.format_message(message, level);
+        self.write_record(record);
+    }
 
-        // Print to stdout
-        print!("{}", message_formatted);
+    fn write_record(&self, record: &Record) {
+        // In WriteMode::Async, the writer thread writes the record to the sinks
+        if let Some(async_writer) = &self.async_writer {
+            let entry = Entry {
+                dispatcher: Arc::clone(&self.dispatcher),
+                record: record.clone(),
+            };
+
+            match async_writer.send(entry) {
+                SendResult::Queued | SendResult::Dropped => (),
+                // Write it here after a shutdown
+                SendResult::Closed(entry) => entry.dispatcher.write(&entry.record),
+            }
+            return;
+        }
 
-        // Open/create log file
-        let log_file_res = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .append(true)
-            .open(&self.log_file_path);
+        self.dispatcher.write(record);
+    }
 
-        if let Ok(mut log_file) = log_file_res {
-            let write_result = log_file.write_all(message_formatted.as_bytes());
+    /// Write the queued and buffered messages of every sink
+    pub fn flush(&self) {
+        if let Some(async_writer) = &self.async_writer {
+            async_writer.wait_idle();
+        }
+        self.dispatcher.flush();
+    }
 
-            if let Err(e) = write_result {
-                eprintln!("Unable to write log message to log file: {}", e);
-            }
-        } else if let Err(e) = log_file_res {
-            eprintln!("Unable to open log file: {}", e)
+    /// Write the queued and buffered messages and stop the writer thread
+    ///
+    /// Messages sent after the shutdown are written synchronously
+    pub fn shutdown(&self) {
+        if let Some(async_writer) = &self.async_writer {
+            async_writer.shutdown();
         }
+        self.dispatcher.flush();
     }
 
     /// Send message of type INFO
+    #[track_caller]
     pub fn info(&self, message: &str) {
         self.log(message, &LogLevel::Info);
     }
 
     /// Send message of type ERROR
+    #[track_caller]
     pub fn error(&self, message: &str) {
         self.log(message, &LogLevel::Error);
     }
 
     /// Send message of type WARN
+    #[track_caller]
     pub fn warn(&self, message: &str) {
         self.log(message, &LogLevel::Warn);
     }
 
     /// Send message of type DEBUG
+    #[track_caller]
     pub fn debug(&self, message: &str) {
         self.log(message, &LogLevel::Debug);
     }
 
     /// Send message of type TRACE
+    #[track_caller]
     pub fn trace(&self, message: &str) {
         self.log(message, &LogLevel::Trace);
     }
 
     /// Send message of type CUSTOM (Defined by user)
+    #[track_caller]
     pub fn custom(&self, message: &str, level: &LogLevel) {
         self.log(message, level);
     }
+
+    /// Send message of type INFO with key/value fields
+    #[track_caller]
+    pub fn info_kv(&self, message: &str, fields: &[(&str, &dyn ToValue)]) {
+        self.log_kv(message, &LogLevel::Info, fields);
+    }
+
+    /// Send message of type ERROR with key/value fields
+    #[track_caller]
+    pub fn error_kv(&self, message: &str, fields: &[(&str, &dyn ToValue)]) {
+        self.log_kv(message, &LogLevel::Error, fields);
+    }
+
+    /// Send message of type WARN with key/value fields
+    #[track_caller]
+    pub fn warn_kv(&self, message: &str, fields: &[(&str, &dyn ToValue)]) {
+        self.log_kv(message, &LogLevel::Warn, fields);
+    }
+
+    /// Send message of type DEBUG with key/value fields
+    #[track_caller]
+    pub fn debug_kv(&self, message: &str, fields: &[(&str, &dyn ToValue)]) {
+        self.log_kv(message, &LogLevel::Debug, fields);
+    }
+
+    /// Send message of type TRACE with key/value fields
+    #[track_caller]
+    pub fn trace_kv(&self, message: &str, fields: &[(&str, &dyn ToValue)]) {
+        self.log_kv(message, &LogLevel::Trace, fields);
+    }
+
+    /// Send message of type CUSTOM (Defined by user) with key/value fields
+    #[track_caller]
+    pub fn custom_kv(&self, message: &str, level: &LogLevel, fields: &[(&str, &dyn ToValue)]) {
+        self.log_kv(message, level, fields);
+    }
+}
+
+/// Allows the Logger to receive records emitted through the `log` crate macros
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.is_enabled_for(&LogLevel::from(metadata.level()), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record) {
+        let location = match (record.file_static(), record.line()) {
+            (Some(file), Some(line)) => Some((file, line)),
+            _ => None,
+        };
+
+        self.log_with(
+            &LogLevel::from(record.level()),
+            record.target(),
+            record.module_path_static(),
+            location,
+            *record.args(),
+            &[],
+        );
+    }
+
+    fn flush(&self) {
+        Logger::flush(self);
+    }
 }
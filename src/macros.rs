@@ -0,0 +1,75 @@
+/// Logs a message with a LogLevel using `format!` arguments
+///
+/// The message is only formatted if the level passes the filter level of the current logger.
+/// The target of the message is the module path of the caller, see `LoggerConfiguration::set_directives`
+///
+/// # Example
+///
+/// ```rust
+/// # use exec_logger::log_level::LogLevel;
+/// let stat = LogLevel::Custom(25, "STAT".to_string());
+/// exec_logger::custom!(&stat, "{} items processed", 42);
+/// ```
+#[macro_export]
+macro_rules! custom {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log::log_args($level, ::std::module_path!(), ::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs a INFO message using `format!` arguments
+///
+/// The message is only formatted if INFO passes the filter level of the current logger
+///
+/// # Example
+///
+/// ```rust
+/// let order_id = "A-123";
+/// exec_logger::info!("Order {} sent after {} retries", order_id, 3);
+/// ```
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::custom!(&$crate::log_level::LogLevel::Info, $($arg)+)
+    };
+}
+
+/// Logs a ERROR message using `format!` arguments
+///
+/// The message is only formatted if ERROR passes the filter level of the current logger
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::custom!(&$crate::log_level::LogLevel::Error, $($arg)+)
+    };
+}
+
+/// Logs a WARN message using `format!` arguments
+///
+/// The message is only formatted if WARN passes the filter level of the current logger
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::custom!(&$crate::log_level::LogLevel::Warn, $($arg)+)
+    };
+}
+
+/// Logs a DEBUG message using `format!` arguments
+///
+/// The message is only formatted if DEBUG passes the filter level of the current logger
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::custom!(&$crate::log_level::LogLevel::Debug, $($arg)+)
+    };
+}
+
+/// Logs a TRACE message using `format!` arguments
+///
+/// The message is only formatted if TRACE passes the filter level of the current logger
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::custom!(&$crate::log_level::LogLevel::Trace, $($arg)+)
+    };
+}
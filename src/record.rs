@@ -0,0 +1,314 @@
+use super::context;
+use super::log_level::LogLevel;
+use chrono::{DateTime, Local};
+use core::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+/// Value of a key/value field attached to a record
+///
+/// Numbers and booleans keep their type in structured formats (e.g. JSON Lines)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(value) => write!(f, "{}", value),
+            Value::I64(value) => write!(f, "{}", value),
+            Value::U64(value) => write!(f, "{}", value),
+            Value::F64(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Conversion of the values accepted as key/value fields
+///
+/// # Example
+///
+/// ```rust
+/// # use exec_logger::record::{ToValue, Value};
+/// let order_id = "A-123".to_string();
+/// let fields: &[(&str, &dyn ToValue)] = &[("order_id", &order_id), ("retries", &3)];
+/// assert_eq!(fields[1].1.to_value(), Value::I64(3));
+/// ```
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Value {
+        Value::Str(self.to_string())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::Str(self.clone())
+    }
+}
+
+impl ToValue for char {
+    fn to_value(&self) -> Value {
+        Value::Str(self.to_string())
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+macro_rules! impl_to_value {
+    ($variant:ident, $target:ty, $($t:ty),*) => {
+        $(
+            impl ToValue for $t {
+                fn to_value(&self) -> Value {
+                    Value::$variant(*self as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_value!(I64, i64, i8, i16, i32, i64, isize);
+impl_to_value!(U64, u64, u8, u16, u32, u64, usize);
+impl_to_value!(F64, f64, f64);
+
+// Go through the shortest decimal form of the f32, a plain cast would show 0.1 as
+// 0.10000000149011612
+impl ToValue for f32 {
+    fn to_value(&self) -> Value {
+        Value::F64(self.to_string().parse().unwrap_or(*self as f64))
+    }
+}
+
+// Write the pairs as key=value separated by spaces
+fn pairs_to_string(pairs: &[(String, Value)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Next thread number, the threads are numbered from 1 in the order of their first message
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+// Number of the current thread, ThreadId does not expose its value on stable Rust
+fn current_thread_id() -> u64 {
+    THREAD_ID.try_with(|id| *id).unwrap_or(0)
+}
+
+/// A single log message with all the information that can be used to format it
+#[derive(Clone, Debug)]
+pub struct Record {
+    timestamp: DateTime<Local>,    // Time the message was sent
+    level: LogLevel,               // Level of the message
+    target: String,                // Module path of the sender, empty if unknown
+    message: String,               // Message sent by the user
+    spans: Vec<String>,            // Spans in which the message was sent, from root to leaf
+    fields: Vec<(String, Value)>,  // Extra key/value fields of the message
+    context: Vec<(String, Value)>, // Context fields of the sender thread, see log::with_context
+    thread_name: Option<String>,   // Name of the sender thread
+    thread_id: u64,                // Number of the sender thread, 0 if unknown
+    file: Option<&'static str>,    // Source file of the call site
+    line: Option<u32>,             // Line of the call site
+    module: Option<&'static str>,  // Module path of the call site
+}
+
+impl Record {
+    /// Create a record sent now, without thread or context, see with_current_thread
+    pub fn new(level: LogLevel, message: &str) -> Self {
+        Record {
+            timestamp: Local::now(),
+            level,
+            target: String::new(),
+            message: message.to_string(),
+            spans: Vec::new(),
+            fields: Vec::new(),
+            context: Vec::new(),
+            thread_name: None,
+            thread_id: 0,
+            file: None,
+            line: None,
+            module: None,
+        }
+    }
+
+    /// Set the name, the number and the context fields of the current thread
+    ///
+    /// The logger calls it once the record passed the filters, so the filtered out messages
+    /// do not pay for it
+    pub fn with_current_thread(mut self) -> Self {
+        self.context = context::current();
+        self.thread_name = thread::current().name().map(String::from);
+        self.thread_id = current_thread_id();
+        self
+    }
+
+    /// Set the target of the record, usually the module path of the sender (e.g. my_crate::db)
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = target.to_string();
+        self
+    }
+
+    /// Set the source file and line of the call site
+    pub fn with_location(mut self, file: &'static str, line: u32) -> Self {
+        self.file = Some(file);
+        self.line = Some(line);
+        self
+    }
+
+    /// Set the module path of the call site
+    pub fn with_module(mut self, module: &'static str) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    /// Set the spans of the record, from the root span to the current span
+    pub fn with_spans(mut self, spans: Vec<String>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Set the extra key/value fields of the record
+    pub fn with_fields(mut self, fields: Vec<(String, Value)>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn get_timestamp(&self) -> &DateTime<Local> {
+        &self.timestamp
+    }
+
+    pub fn get_level(&self) -> &LogLevel {
+        &self.level
+    }
+
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_spans(&self) -> &[String] {
+        &self.spans
+    }
+
+    pub fn get_fields(&self) -> &[(String, Value)] {
+        &self.fields
+    }
+
+    pub fn get_thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    pub fn get_thread_id(&self) -> u64 {
+        self.thread_id
+    }
+
+    pub fn get_file(&self) -> Option<&'static str> {
+        self.file
+    }
+
+    pub fn get_line(&self) -> Option<u32> {
+        self.line
+    }
+
+    pub fn get_module(&self) -> Option<&'static str> {
+        self.module
+    }
+
+    pub fn get_context(&self) -> &[(String, Value)] {
+        &self.context
+    }
+
+    /// Spans joined by ':' as shown by the {SPANS} keyword
+    pub fn spans_to_string(&self) -> String {
+        self.spans.join(":")
+    }
+
+    /// Fields written as key=value separated by spaces as shown by the {FIELDS} keyword
+    pub fn fields_to_string(&self) -> String {
+        pairs_to_string(&self.fields)
+    }
+
+    /// Context fields written as key=value separated by spaces as shown by the {CONTEXT} keyword
+    pub fn context_to_string(&self) -> String {
+        pairs_to_string(&self.context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_value() {
+        assert_eq!("a".to_value(), Value::Str("a".to_string()));
+        assert_eq!('c'.to_value(), Value::Str("c".to_string()));
+        assert_eq!((-3i8).to_value(), Value::I64(-3));
+        assert_eq!(3usize.to_value(), Value::U64(3));
+        assert_eq!(0.5f64.to_value(), Value::F64(0.5));
+        assert_eq!(0.1f32.to_value(), Value::F64(0.1));
+        assert_eq!(0.1f32.to_value().to_string(), "0.1");
+        assert_eq!(true.to_value(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_current_thread() {
+        let record = Record::new(LogLevel::Info, "message");
+        assert_eq!(record.get_thread_id(), 0);
+
+        let record = record.with_current_thread();
+        let id = record.get_thread_id();
+        assert!(id > 0);
+        assert_eq!(current_thread_id(), id);
+
+        let other = thread::spawn(current_thread_id).join().unwrap();
+        assert!(other > 0);
+        assert_ne!(other, id);
+    }
+
+    #[test]
+    fn test_value_display() {
+        let fields = vec![
+            ("name".to_string(), Value::Str("a b".to_string())),
+            ("count".to_string(), Value::I64(-2)),
+            ("ratio".to_string(), Value::F64(0.25)),
+            ("ok".to_string(), Value::Bool(false)),
+        ];
+        let record = Record::new(LogLevel::Info, "message").with_fields(fields);
+        assert_eq!(
+            record.fields_to_string(),
+            "name=a b count=-2 ratio=0.25 ok=false"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use super::config::{Color, ColorMode, ConsoleTarget, LoggerConfiguration, OutputFormat};
+use super::format::{self, Execution};
+use super::formatter::Formatter;
+use super::log_level::LogLevel;
+use super::record::Record;
+use core::fmt;
+use std::io::IsTerminal;
+use std::sync::Arc;
+
+/// Destination of the log messages
+///
+/// The logger writes each record to every sink whose filter level accepts it. The level of a
+/// sink replaces the filter level of the logger, so a sink can be more verbose than the logger,
+/// and the directives with a target apply to every sink.
+/// The built-in sinks are the console (see `ConsoleTarget`) and the log file of the execution,
+/// other destinations can be added with `LoggerConfiguration::add_sink`
+///
+/// # Example
+///
+/// ```rust
+/// use exec_logger::record::Record;
+/// use exec_logger::sink::Sink;
+/// use std::sync::Mutex;
+///
+/// // Keeps the messages in memory
+/// #[derive(Default)]
+/// struct MemorySink {
+///     messages: Mutex<Vec<String>>,
+/// }
+///
+/// impl Sink for MemorySink {
+///     fn write(&self, _record: &Record, message: &str) {
+///         self.messages.lock().unwrap().push(message.to_string());
+///     }
+/// }
+/// ```
+pub trait Sink: Send + Sync {
+    /// Write a record, `message` is the record formatted with the output format of the sink
+    fn write(&self, record: &Record, message: &str);
+
+    /// Write the buffered messages, called by `exec_logger::log::flush`
+    fn flush(&self) {}
+}
+
+impl Color {
+    // Select Graphic Rendition parameter of the foreground color
+    fn ansi_code(&self) -> String {
+        match self {
+            Color::Black => "30".to_string(),
+            Color::Red => "31".to_string(),
+            Color::Green => "32".to_string(),
+            Color::Yellow => "33".to_string(),
+            Color::Blue => "34".to_string(),
+            Color::Magenta => "35".to_string(),
+            Color::Cyan => "36".to_string(),
+            Color::White => "37".to_string(),
+            Color::BrightBlack => "90".to_string(),
+            Color::Fixed(x) => format!("38;5;{}", x),
+        }
+    }
+}
+
+// Check if the messages printed to a stream can be colored
+fn use_color<T: IsTerminal>(color_mode: &ColorMode, stream: T) -> bool {
+    match color_mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty());
+            !no_color && stream.is_terminal()
+        }
+    }
+}
+
+/// Sink that prints the messages to stdout or stderr
+#[derive(Clone, Debug)]
+pub struct ConsoleSink {
+    target: ConsoleTarget,
+    stdout_color: bool,                   // Color the messages printed to stdout
+    stderr_color: bool,                   // Color the messages printed to stderr
+    level_colors: Vec<(LogLevel, Color)>, // Color of the messages of each level
+}
+
+impl ConsoleSink {
+    /// Console sink without colors
+    pub fn new(target: ConsoleTarget) -> Self {
+        ConsoleSink {
+            target,
+            stdout_color: false,
+            stderr_color: false,
+            level_colors: Vec::new(),
+        }
+    }
+
+    /// Color the messages of the levels, see `LoggerConfiguration::set_level_color`
+    pub fn with_colors(
+        mut self,
+        color_mode: &ColorMode,
+        level_colors: Vec<(LogLevel, Color)>,
+    ) -> Self {
+        self.stdout_color = use_color(color_mode, std::io::stdout());
+        self.stderr_color = use_color(color_mode, std::io::stderr());
+        self.level_colors = level_colors;
+        self
+    }
+
+    // Add the color of the level around the message, the new line is left uncolored
+    fn print(&self, record: &Record, message: &str, to_stderr: bool) {
+        let colored = if to_stderr {
+            self.stderr_color
+        } else {
+            self.stdout_color
+        };
+        let color = self
+            .level_colors
+            .iter()
+            .find(|(level, _)| level == record.get_level())
+            .map(|(_, color)| color);
+
+        let message = match color {
+            Some(color) if colored => format!(
+                "\x1b[{}m{}\x1b[0m\n",
+                color.ansi_code(),
+                message.trim_end_matches('\n')
+            ),
+            _ => message.to_string(),
+        };
+
+        if to_stderr {
+            eprint!("{}", message);
+        } else {
+            print!("{}", message);
+        }
+    }
+}
+
+impl Default for ConsoleSink {
+    fn default() -> Self {
+        ConsoleSink::new(ConsoleTarget::Stdout)
+    }
+}
+
+impl Sink for ConsoleSink {
+    fn write(&self, record: &Record, message: &str) {
+        match &self.target {
+            ConsoleTarget::Stdout => self.print(record, message, false),
+            ConsoleTarget::Stderr => self.print(record, message, true),
+            ConsoleTarget::Split(level) => self.print(record, message, record.get_level() >= level),
+            ConsoleTarget::Disabled => (),
+        }
+    }
+}
+
+/// A sink with its own filter level, output format and formatter
+#[derive(Clone)]
+pub(crate) struct SinkEntry {
+    sink: Arc<dyn Sink>,
+    filter_level: Option<LogLevel>, // Lowest severity written to the sink, replaces the filter level
+    output_format: Option<OutputFormat>, // Output format of the logger if None
+    formatter: Option<Arc<dyn Formatter>>, // Formatter or message format of the logger if None
+}
+
+impl SinkEntry {
+    pub(crate) fn new(
+        sink: Arc<dyn Sink>,
+        filter_level: Option<LogLevel>,
+        output_format: Option<OutputFormat>,
+        formatter: Option<Arc<dyn Formatter>>,
+    ) -> Self {
+        SinkEntry {
+            sink,
+            filter_level,
+            output_format,
+            formatter,
+        }
+    }
+}
+
+impl fmt::Debug for SinkEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SinkEntry")
+            .field("filter_level", &self.filter_level)
+            .field("output_format", &self.output_format)
+            .field("formatter", &self.formatter)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Formats the records and writes them to every sink
+#[derive(Debug)]
+pub(crate) struct Dispatcher {
+    config: LoggerConfiguration,
+    execution: Execution,
+    sinks: Vec<SinkEntry>,
+    default_level: Threshold, // Level of the sinks without their own level
+    lowest_level: Threshold,  // Lowest severity written to at least one sink
+}
+
+// Lowest severity written to a sink for the records that match no directive with a target
+#[derive(Clone, Debug, PartialEq)]
+enum Threshold {
+    Every,          // Every level is written
+    From(LogLevel), // The levels from this one are written
+    Off,            // Nothing is written
+}
+
+impl Threshold {
+    fn accepts(&self, level: &LogLevel) -> bool {
+        match self {
+            Threshold::Every => true,
+            Threshold::From(lowest) => level >= lowest,
+            Threshold::Off => false,
+        }
+    }
+
+    // Threshold that accepts the levels accepted by either threshold
+    fn lowest(self, other: Threshold) -> Threshold {
+        match (self, other) {
+            (Threshold::Every, _) | (_, Threshold::Every) => Threshold::Every,
+            (Threshold::Off, x) | (x, Threshold::Off) => x,
+            (Threshold::From(a), Threshold::From(b)) => Threshold::From(a.min(b)),
+        }
+    }
+}
+
+impl Dispatcher {
+    pub(crate) fn new(
+        config: LoggerConfiguration,
+        execution: Execution,
+        sinks: Vec<SinkEntry>,
+    ) -> Self {
+        // The sinks without their own level use the directive without target, otherwise the
+        // filter level
+        let default_level = match config.get_default_directive() {
+            Some(directive) => match &directive.level {
+                Some(level) => Threshold::From(level.clone()),
+                None => Threshold::Off,
+            },
+            None => match config.get_filter_level() {
+                Some(level) => Threshold::From(level),
+                None => Threshold::Every,
+            },
+        };
+        let lowest_level = sinks
+            .iter()
+            .map(|entry| match &entry.filter_level {
+                Some(level) => Threshold::From(level.clone()),
+                None => default_level.clone(),
+            })
+            .fold(Threshold::Off, Threshold::lowest);
+
+        Dispatcher {
+            config,
+            execution,
+            sinks,
+            default_level,
+            lowest_level,
+        }
+    }
+
+    /// Check if a record that matches no directive with a target is written to at least one sink
+    ///
+    /// Records that fail it can be dropped before being created
+    pub(crate) fn is_enabled(&self, level: &LogLevel) -> bool {
+        self.lowest_level.accepts(level)
+    }
+
+    /// Write the record to the sinks that accept its level
+    ///
+    /// The record already passed the directive with a target that matches it, if there is one,
+    /// so only the levels of the sinks are checked. Otherwise the sinks without their own level
+    /// use the directive without target, or the filter level
+    pub(crate) fn write(&self, record: &Record) {
+        // Each output format is formatted at most once
+        let mut text: Option<String> = None;
+        let mut json: Option<String> = None;
+        let default_format = self.config.get_output_format();
+        let default_level = match self.config.get_target_directive(record.get_target()) {
+            Some(_) => &Threshold::Every,
+            None => &self.default_level,
+        };
+
+        for entry in &self.sinks {
+            let accepted = match &entry.filter_level {
+                Some(filter_level) => record.get_level() >= filter_level,
+                None => default_level.accepts(record.get_level()),
+            };
+            if !accepted {
+                continue;
+            }
+
+            let output_format = entry.output_format.as_ref().unwrap_or(&default_format);
+
+            // The text of a sink with its own formatter is not shared with the other sinks
+            if let (OutputFormat::Text, Some(formatter)) = (output_format, &entry.formatter) {
+                let message = format::format_record(
+                    &self.config,
+                    &self.execution,
+                    record,
+                    output_format,
+                    Some(formatter.as_ref()),
+                );
+                entry.sink.write(record, &message);
+                continue;
+            }
+
+            let cache = match output_format {
+                OutputFormat::Text => &mut text,
+                OutputFormat::JsonLines => &mut json,
+            };
+            let message = cache.get_or_insert_with(|| {
+                format::format_record(&self.config, &self.execution, record, output_format, None)
+            });
+
+            entry.sink.write(record, message);
+        }
+    }
+
+    /// Write the buffered messages of every sink
+    pub(crate) fn flush(&self) {
+        for entry in &self.sinks {
+            entry.sink.flush();
+        }
+    }
+}
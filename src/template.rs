@@ -0,0 +1,262 @@
+use super::config::ConfigError;
+
+/// Keyword of the message format, see LoggerConfiguration::set_message_format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Keyword {
+    Timestamp,
+    ExeName,
+    SystemName,
+    UserName,
+    Level,
+    Message,
+    Target,
+    Spans,
+    Fields,
+    Context,
+    ThreadName,
+    ThreadId,
+    Pid,
+    ExecutionId,
+    Elapsed,
+    File,
+    Line,
+    Module,
+}
+
+impl Keyword {
+    fn from_name(name: &str) -> Option<Keyword> {
+        match name {
+            "TIMESTAMP" => Some(Keyword::Timestamp),
+            "EXE_NAME" => Some(Keyword::ExeName),
+            "SYSTEM_NAME" => Some(Keyword::SystemName),
+            "USER_NAME" => Some(Keyword::UserName),
+            "LEVEL" => Some(Keyword::Level),
+            "MESSAGE" => Some(Keyword::Message),
+            "TARGET" => Some(Keyword::Target),
+            "SPANS" => Some(Keyword::Spans),
+            "FIELDS" => Some(Keyword::Fields),
+            "CONTEXT" => Some(Keyword::Context),
+            "THREAD_NAME" => Some(Keyword::ThreadName),
+            "THREAD_ID" => Some(Keyword::ThreadId),
+            "PID" => Some(Keyword::Pid),
+            "EXECUTION_ID" => Some(Keyword::ExecutionId),
+            "ELAPSED" => Some(Keyword::Elapsed),
+            "FILE" => Some(Keyword::File),
+            "LINE" => Some(Keyword::Line),
+            "MODULE" => Some(Keyword::Module),
+            _ => None,
+        }
+    }
+
+    // Width used when the format does not give one, {LEVEL} has always been padded to 7
+    fn default_width(&self) -> Option<usize> {
+        match self {
+            Keyword::Level => Some(7),
+            _ => None,
+        }
+    }
+}
+
+/// Alignment of a value inside its width
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Part of a compiled message format
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    Literal(String),
+    Keyword {
+        keyword: Keyword,
+        width: Option<usize>,
+        align: Align,
+    },
+}
+
+/// Message format parsed once, so the records are formatted in a single pass
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Template {
+    tokens: Vec<Token>,
+}
+
+// Error at a position (in characters) of the format
+fn invalid(position: usize, details: &str) -> ConfigError {
+    ConfigError::InvalidFormat(format!("{} at position {}", details, position))
+}
+
+// Parse the specifier after ':', e.g. "<7", ">10", "^5" or "7"
+fn parse_spec(spec: &str, position: usize) -> Result<(usize, Align), ConfigError> {
+    let (align, width) = match spec.chars().next() {
+        Some('<') => (Align::Left, &spec[1..]),
+        Some('>') => (Align::Right, &spec[1..]),
+        Some('^') => (Align::Center, &spec[1..]),
+        _ => (Align::Left, spec),
+    };
+
+    match width.parse::<usize>() {
+        Ok(width) => Ok((width, align)),
+        Err(_) => Err(invalid(
+            position,
+            &format!("Invalid width specifier '{}'", spec),
+        )),
+    }
+}
+
+impl Template {
+    /// Parse a message format
+    ///
+    /// Keywords are written {KEYWORD} or {KEYWORD:<width}, {KEYWORD:>width}, {KEYWORD:^width}.
+    /// Literal braces are written {{ and }}
+    pub(crate) fn parse(format: &str) -> Result<Template, ConfigError> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = format.chars().enumerate().peekable();
+
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, x)| *x == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|(_, x)| *x == '}').is_some() => literal.push('}'),
+                '}' => return Err(invalid(position, "Unmatched '}'")),
+                '{' => {
+                    let mut content = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, '{')) | None => {
+                                return Err(invalid(position, "Unclosed '{'"));
+                            }
+                            Some((_, x)) => content.push(x),
+                        }
+                    }
+
+                    let (name, spec) = match content.split_once(':') {
+                        Some((name, spec)) => (name, Some(spec)),
+                        None => (content.as_str(), None),
+                    };
+                    let keyword = match Keyword::from_name(name) {
+                        Some(x) => x,
+                        None => {
+                            return Err(ConfigError::UnknownKeyword {
+                                keyword: name.to_string(),
+                                position,
+                            });
+                        }
+                    };
+                    let (width, align) = match spec {
+                        Some(spec) => {
+                            let (width, align) = parse_spec(spec, position)?;
+                            (Some(width), align)
+                        }
+                        None => (keyword.default_width(), Align::Left),
+                    };
+
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(Token::Keyword {
+                        keyword,
+                        width,
+                        align,
+                    });
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        Ok(Template { tokens })
+    }
+
+    /// Return the parts of the format, in order
+    pub(crate) fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Return true if the keyword is used in the format
+    pub(crate) fn contains(&self, keyword: Keyword) -> bool {
+        self.tokens
+            .iter()
+            .any(|x| matches!(x, Token::Keyword { keyword: k, .. } if *k == keyword))
+    }
+}
+
+/// Append the value, padded with spaces to the width
+pub(crate) fn push_aligned(msg: &mut String, value: &str, width: Option<usize>, align: Align) {
+    let padding = width
+        .map(|width| width.saturating_sub(value.chars().count()))
+        .unwrap_or(0);
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+
+    msg.push_str(&" ".repeat(before));
+    msg.push_str(value);
+    msg.push_str(&" ".repeat(after));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyword(keyword: Keyword, width: Option<usize>, align: Align) -> Token {
+        Token::Keyword {
+            keyword,
+            width,
+            align,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let template = Template::parse("{{{LEVEL}}} {PID:>8}|{TARGET:^4}{MESSAGE:3}").unwrap();
+        assert_eq!(
+            template.tokens(),
+            [
+                Token::Literal("{".to_string()),
+                keyword(Keyword::Level, Some(7), Align::Left),
+                Token::Literal("} ".to_string()),
+                keyword(Keyword::Pid, Some(8), Align::Right),
+                Token::Literal("|".to_string()),
+                keyword(Keyword::Target, Some(4), Align::Center),
+                keyword(Keyword::Message, Some(3), Align::Left),
+            ]
+        );
+        assert!(template.contains(Keyword::Pid));
+        assert!(!template.contains(Keyword::File));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        match Template::parse("ab {LEVEL} {NOPE}") {
+            Err(ConfigError::UnknownKeyword { keyword, position }) => {
+                assert_eq!(keyword, "NOPE");
+                assert_eq!(position, 11);
+            }
+            x => panic!("Unexpected result {:?}", x),
+        }
+        assert!(Template::parse("{LEVEL").is_err());
+        assert!(Template::parse("{LE{VEL}").is_err());
+        assert!(Template::parse("LEVEL}").is_err());
+        assert!(Template::parse("{LEVEL:<}").is_err());
+        assert!(Template::parse("{LEVEL:-3}").is_err());
+    }
+
+    #[test]
+    fn test_push_aligned() {
+        let mut msg = String::new();
+        push_aligned(&mut msg, "ab", Some(6), Align::Left);
+        push_aligned(&mut msg, "ab", Some(6), Align::Right);
+        push_aligned(&mut msg, "ab", Some(5), Align::Center);
+        push_aligned(&mut msg, "long", Some(2), Align::Left);
+        push_aligned(&mut msg, "é", Some(2), Align::Left);
+        assert_eq!(msg, "ab        ab ab  longé ");
+    }
+}
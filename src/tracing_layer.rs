@@ -0,0 +1,170 @@
+use super::log::get_logger;
+use super::log_level::LogLevel;
+use super::record::{Record, Value};
+use core::fmt;
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record as SpanRecord};
+use tracing_core::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// `tracing_subscriber::Layer` that writes the `tracing` events to the current log file
+///
+/// The events are sent to the logger set by `exec_logger::log::initialize`, so the layer keeps
+/// working after the logger is re-initialized.
+///
+/// The spans of the event are available with the {SPANS} keyword and the fields of the
+/// event are available with the {FIELDS} keyword of the message format
+///
+/// # Example
+///
+/// ```rust
+/// use exec_logger::tracing_layer::ExecLoggerLayer;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let subscriber = tracing_subscriber::registry().with(ExecLoggerLayer::new());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ExecLoggerLayer;
+
+impl ExecLoggerLayer {
+    pub fn new() -> Self {
+        ExecLoggerLayer
+    }
+}
+
+// Fields of a span, stored in the span extensions
+struct SpanFields(Vec<(String, Value)>);
+
+// Collects the message and the fields of events and spans
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Vec<(String, Value)>,
+}
+
+impl FieldVisitor {
+    fn push(&mut self, field: &Field, value: Value) {
+        self.fields.push((field.name().to_string(), value));
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Value::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, Value::U64(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, Value::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Value::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.push(field, Value::Str(value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{:?}", value));
+        } else {
+            self.push(field, Value::Str(format!("{:?}", value)));
+        }
+    }
+}
+
+// Write a span as name{key=value ...}
+fn format_span(name: &str, fields: &[(String, Value)]) -> String {
+    if fields.is_empty() {
+        return name.to_string();
+    }
+
+    let fields = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!("{}{{{}}}", name, fields)
+}
+
+impl<S> Layer<S> for ExecLoggerLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldVisitor::default();
+            attrs.record(&mut visitor);
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &SpanRecord<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldVisitor::default();
+            values.record(&mut visitor);
+
+            let mut extensions = span.extensions_mut();
+            if let Some(span_fields) = extensions.get_mut::<SpanFields>() {
+                span_fields.0.extend(visitor.fields);
+            } else {
+                extensions.insert(SpanFields(visitor.fields));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let logger = match get_logger() {
+            Some(logger) => logger,
+            None => return,
+        };
+
+        let level = LogLevel::from(event.metadata().level());
+        let target = event.metadata().target();
+        if !logger.is_enabled_for(&level, target) {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut spans = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                let extensions = span.extensions();
+                let fields = match extensions.get::<SpanFields>() {
+                    Some(span_fields) => &span_fields.0[..],
+                    None => &[],
+                };
+                spans.push(format_span(span.name(), fields));
+            }
+        }
+
+        let message = visitor.message.unwrap_or_default();
+        let metadata = event.metadata();
+        let mut record = Record::new(level, &message)
+            .with_target(target)
+            .with_spans(spans)
+            .with_fields(visitor.fields)
+            .with_current_thread();
+        if let (Some(file), Some(line)) = (metadata.file(), metadata.line()) {
+            record = record.with_location(file, line);
+        }
+        if let Some(module) = metadata.module_path() {
+            record = record.with_module(module);
+        }
+
+        logger.log_record(&record);
+    }
+}
@@ -0,0 +1,87 @@
+use super::config_file::ConfigFile;
+use super::log;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Reloads the configuration of the current logger when a configuration file changes
+///
+/// The file is checked every `interval` and read like `LoggerConfiguration::from_file`.
+/// Only the options present in the file are applied, the others keep their running value,
+/// e.g. a ConsoleTarget set in the code. The changes are applied like `log::reload`, so the
+/// logger keeps writing to the same execution folder. An invalid file is logged as a warning
+/// and the previous configuration stays active. The watcher stops when it is dropped
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exec_logger::config::LoggerConfiguration;
+/// use exec_logger::log;
+/// use exec_logger::watcher::ConfigWatcher;
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// let path = Path::new("logger.toml");
+/// log::initialize(LoggerConfiguration::from_file(path).unwrap()).unwrap();
+/// let _watcher = ConfigWatcher::new(path, Duration::from_secs(5)).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    stop: Option<Sender<()>>, // Dropped to stop the thread
+    thread: Option<JoinHandle<()>>,
+}
+
+// Last modification time and size of the file, None if it cannot be read
+fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path, interval: Duration) -> Result<Self, std::io::Error> {
+        let path = PathBuf::from(path);
+        let (stop, stopped) = mpsc::channel::<()>();
+        // Read before starting the thread, so a change made right after new returns is detected
+        let mut last_modified = modified(&path);
+
+        let thread = thread::Builder::new()
+            .name("exec_logger_watcher".to_string())
+            .spawn(move || {
+                // Stops when the sender is dropped
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let current_modified = modified(&path);
+                    // The file may be missing while it is being replaced
+                    if current_modified.is_none() || current_modified == last_modified {
+                        continue;
+                    }
+                    last_modified = current_modified;
+
+                    match ConfigFile::read(&path).and_then(|file| log::reload_file(&file)) {
+                        Ok(()) => {
+                            log::info(&format!("Configuration reloaded from {}", path.display()));
+                        }
+                        Err(e) => log::warn(&format!(
+                            "Invalid configuration file {}, the configuration is unchanged: {}",
+                            path.display(),
+                            e
+                        )),
+                    }
+                }
+            })?;
+
+        Ok(ConfigWatcher {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
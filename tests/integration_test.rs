@@ -1,37 +1,57 @@
 use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use std::time::Duration;
 use std::{path::PathBuf, thread::sleep};
 
 use exec_logger::log_level::LogLevel;
-use exec_logger::{config, log, log_level};
+use exec_logger::{config, formatter, log, log_level, logger};
+
+// The logger is a global instance, tests that initialize it must not run concurrently
+static GLOBAL_LOGGER_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_global_logger() -> MutexGuard<'static, ()> {
+    GLOBAL_LOGGER_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Empty directory used by a single test, unique per process so runs in the same second
+// do not share execution folders
+fn test_dir(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join("exec_logger_tests").join(format!(
+        "{}_{}_{}",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Configuration writing to a new test directory, with the values used by most tests
+fn new_config(name: &str, filter_level: Option<LogLevel>) -> config::LoggerConfiguration {
+    config::LoggerConfiguration::new(test_dir(name), "LOG", Some(7), Some(5), filter_level)
+}
 
 #[test]
 fn test_config() {
-    let config = config::LoggerConfiguration::new(
-        PathBuf::from("test_files"),
-        "LOG",
-        Some(7),
-        Some(5),
-        Some(log_level::LogLevel::Info),
-    );
+    let config = new_config("config", Some(log_level::LogLevel::Info));
     println!("Config created = {:?}", config);
 }
 
 #[test]
 fn test_log() {
-    let config = config::LoggerConfiguration::new(
-        PathBuf::from("test_files"),
-        "LOG",
-        Some(7),
-        Some(5),
-        Some(log_level::LogLevel::Info),
-    );
+    let _guard = lock_global_logger();
+    let config = new_config("log", Some(log_level::LogLevel::Info));
     println!("Config created = {:?}", config);
 
     // Try to send an INFO message before initializing
     log::info("Message 1");
 
-    log::initialize(config);
+    log::initialize(config).unwrap();
 
     // Try to send an INFO after initializing
     log::info("Message 2");
@@ -70,9 +90,10 @@ fn test_log() {
 
 #[test]
 fn test_multiple_initialization() {
+    let _guard = lock_global_logger();
     for i in 1..6 {
         let mut config = config::LoggerConfiguration::new(
-            PathBuf::from(format!("test_files/{}", i)),
+            test_dir(&format!("multiple_initialization_{}", i)),
             "txt",
             Some(7),
             Some(50),
@@ -82,24 +103,19 @@ fn test_multiple_initialization() {
         let _ = config.set_message_format("{USER_NAME} | {LEVEL} | {MESSAGE} | {TIMESTAMP}");
 
         let _ = config.set_timestamp_format("%y-%m-%d");
-        log::initialize(config);
+        log::initialize(config).unwrap();
         sleep(Duration::from_secs(1));
     }
 }
 
 #[test]
 fn test_custom_level() {
+    let _guard = lock_global_logger();
     let stat = log::create_custom_level("STAT", 25);
 
-    let config = config::LoggerConfiguration::new(
-        PathBuf::from("test_files"),
-        "LOG",
-        Some(7),
-        Some(5),
-        None,
-    );
+    let config = new_config("custom_level", None);
 
-    log::initialize(config);
+    log::initialize(config).unwrap();
 
     log::custom("This is a STAT message", &stat);
 
@@ -108,17 +124,12 @@ fn test_custom_level() {
 
 #[test]
 fn test_level_severity() {
-    let config = config::LoggerConfiguration::new(
-        PathBuf::from("test_files"),
-        "LOG",
-        Some(7),
-        Some(5),
-        None,
-    );
+    let _guard = lock_global_logger();
+    let config = new_config("level_severity", None);
 
     let custom1 = log::create_custom_level("CUSTOM1", 45);
 
-    log::initialize(config);
+    log::initialize(config).unwrap();
 
     log::custom("This is a custom message", &custom1);
 
@@ -135,3 +146,1284 @@ fn test_level_severity() {
     log::error(&format!("ERROR = {}", i32::from(&LogLevel::Error)));
     log::custom(&format!("CUSTOM1 = {}", i32::from(&custom1)), &custom1);
 }
+
+#[test]
+fn test_log_facade() {
+    let _guard = lock_global_logger();
+    let config = new_config("facade", Some(log_level::LogLevel::Info));
+
+    log::initialize(config).unwrap();
+    log::install_log_facade().unwrap();
+
+    ::log::warn!("Facade message 1");
+    ::log::debug!("Facade message 2");
+
+    let contents = fs::read_to_string(log::get_log_file_path().unwrap()).unwrap();
+    assert!(
+        contents.contains("WARN    | Facade message 1"),
+        "Facade message 1 should be present"
+    );
+    assert!(
+        !contents.contains("Facade message 2"),
+        "Facade message 2 should not be present"
+    );
+
+    // The facade can only be installed once
+    assert!(log::install_log_facade().is_err());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_layer() {
+    use exec_logger::tracing_layer::ExecLoggerLayer;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let _guard = lock_global_logger();
+    let mut config = new_config("tracing", Some(log_level::LogLevel::Info));
+    config
+        .set_message_format("{LEVEL} | {SPANS} | {MESSAGE} | {FIELDS}")
+        .unwrap();
+
+    log::initialize(config).unwrap();
+
+    let subscriber = tracing_subscriber::registry().with(ExecLoggerLayer::new());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("request", id = 42);
+        let _entered = span.enter();
+        tracing::info!(user = "alice", retries = 3, "Tracing message 1");
+        tracing::debug!("Tracing message 2");
+    });
+
+    let contents = fs::read_to_string(log::get_log_file_path().unwrap()).unwrap();
+    assert!(
+        contents.contains("INFO    | request{id=42} | Tracing message 1 | user=alice retries=3"),
+        "Tracing message 1 should be present with its span and fields"
+    );
+    assert!(
+        !contents.contains("Tracing message 2"),
+        "Tracing message 2 should not be present"
+    );
+}
+
+#[test]
+fn test_flush_policy() {
+    let mut config = new_config("flush", None);
+    // A flush thread without interval would never sleep
+    assert!(config
+        .set_flush_policy(config::FlushPolicy::Interval(Duration::ZERO))
+        .is_err());
+    config
+        .set_flush_policy(config::FlushPolicy::Severity(LogLevel::Error))
+        .unwrap();
+
+    let logger = logger::Logger::new(config).unwrap();
+    let log_file_path = logger.get_log_file_path();
+
+    logger.info("Buffered message 1");
+    let contents = fs::read_to_string(&log_file_path).unwrap_or_default();
+    assert!(
+        !contents.contains("Buffered message 1"),
+        "Buffered message 1 should not be flushed yet"
+    );
+
+    logger.error("Buffered message 2");
+    let contents = fs::read_to_string(&log_file_path).unwrap();
+    assert!(
+        contents.contains("Buffered message 1") && contents.contains("Buffered message 2"),
+        "Buffered messages 1 and 2 should be flushed by the ERROR"
+    );
+
+    logger.info("Buffered message 3");
+    drop(logger);
+    let contents = fs::read_to_string(&log_file_path).unwrap();
+    assert!(
+        contents.contains("Buffered message 3"),
+        "Buffered message 3 should be flushed on drop"
+    );
+
+    // The interval is checked by a background thread, even if no other message is written
+    let mut config = new_config("flush_interval", None);
+    config
+        .set_flush_policy(config::FlushPolicy::Interval(Duration::from_millis(100)))
+        .unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+    logger.info("Interval message 1");
+    sleep(Duration::from_millis(500));
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(
+        contents.contains("Interval message 1"),
+        "Interval message 1 should be flushed by the timer"
+    );
+}
+
+#[test]
+fn test_async_writer() {
+    let mut config = new_config("async", None);
+    config
+        .set_write_mode(config::WriteMode::Async {
+            capacity: 4,
+            overflow_policy: config::OverflowPolicy::Block,
+        })
+        .unwrap();
+
+    let logger = logger::Logger::new(config).unwrap();
+    for i in 0..100 {
+        logger.info(&format!("Async message {}", i));
+    }
+
+    logger.flush();
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert_eq!(
+        contents.matches("Async message").count(),
+        100,
+        "Every async message should be written after flush"
+    );
+
+    logger.shutdown();
+    logger.info("Message after shutdown");
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(
+        contents.contains("Message after shutdown"),
+        "Messages after shutdown should be written synchronously"
+    );
+
+    // A queue without capacity is rejected
+    let mut config = config::LoggerConfiguration::default();
+    assert!(config
+        .set_write_mode(config::WriteMode::Async {
+            capacity: 0,
+            overflow_policy: config::OverflowPolicy::DropNewest,
+        })
+        .is_err());
+}
+
+#[test]
+fn test_async_drop_below() {
+    use exec_logger::record::Record;
+    use exec_logger::sink::Sink;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    // Blocks the writer thread while the test holds the gate
+    struct GateSink {
+        gate: Arc<Mutex<()>>,
+        entered: Arc<AtomicBool>,
+    }
+
+    impl Sink for GateSink {
+        fn write(&self, _record: &Record, _message: &str) {
+            self.entered.store(true, Ordering::SeqCst);
+            let _gate = self.gate.lock().unwrap();
+        }
+    }
+
+    let gate = Arc::new(Mutex::new(()));
+    let entered = Arc::new(AtomicBool::new(false));
+    let mut config = new_config("async_drop_below", None);
+    config
+        .set_write_mode(config::WriteMode::Async {
+            capacity: 2,
+            overflow_policy: config::OverflowPolicy::DropBelow(LogLevel::Error),
+        })
+        .unwrap();
+    config.add_sink(
+        Arc::new(GateSink {
+            gate: gate.clone(),
+            entered: entered.clone(),
+        }),
+        None,
+        None,
+        None,
+    );
+
+    let closed_gate = gate.lock().unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+    // The writer thread is blocked by the first message, so the queue fills up
+    while !entered.load(Ordering::SeqCst) {
+        sleep(Duration::from_millis(1));
+    }
+    logger.info("Overflow message 1");
+    logger.info("Overflow message 2");
+    logger.error("Overflow message 3"); // Replaces message 1 instead of blocking
+    logger.info("Overflow message 4"); // Dropped, the queue is full
+    drop(closed_gate);
+    logger.flush();
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(!contents.contains("Overflow message 1"));
+    assert!(contents.contains("Overflow message 2"));
+    assert!(contents.contains("Overflow message 3"));
+    assert!(!contents.contains("Overflow message 4"));
+}
+
+#[test]
+fn test_async_sink_panic() {
+    use exec_logger::record::Record;
+    use exec_logger::sink::Sink;
+    use std::sync::Arc;
+
+    // Panics on the first message it receives
+    struct PanicSink {
+        panicked: AtomicUsize,
+    }
+
+    impl Sink for PanicSink {
+        fn write(&self, _record: &Record, _message: &str) {
+            if self.panicked.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("Sink panic");
+            }
+        }
+    }
+
+    let sink = Arc::new(PanicSink {
+        panicked: AtomicUsize::new(0),
+    });
+    let mut config = new_config("async_sink_panic", None);
+    config
+        .set_write_mode(config::WriteMode::Async {
+            capacity: 1,
+            overflow_policy: config::OverflowPolicy::Block,
+        })
+        .unwrap();
+    config.add_sink(sink.clone(), None, None, None);
+
+    let logger = logger::Logger::new(config).unwrap();
+    // The flush returns even though the writer thread died
+    logger.flush();
+    assert_eq!(sink.panicked.load(Ordering::SeqCst), 1);
+
+    // The next messages are written on the caller's thread instead of waiting for the queue
+    for i in 0..3 {
+        logger.info(&format!("Message after panic {}", i));
+    }
+    logger.flush();
+    assert_eq!(sink.panicked.load(Ordering::SeqCst), 4);
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert_eq!(contents.matches("Message after panic").count(), 3);
+}
+
+#[test]
+fn test_rotation() {
+    let mut config = new_config("rotation", None);
+    config
+        .set_rotation_policy(config::RotationPolicy::MaxBytes(300))
+        .unwrap();
+    config.set_max_rotated_files(Some(2));
+
+    let logger = logger::Logger::new(config).unwrap();
+    for i in 0..20 {
+        logger.info(&format!("Rotated message {}", i));
+    }
+
+    let log_file_path = logger.get_log_file_path();
+    let log_dir = log_file_path.parent().unwrap();
+    assert!(log_file_path.exists());
+    assert!(log_dir.join("execution_log.1.LOG").exists());
+    assert!(log_dir.join("execution_log.2.LOG").exists());
+    assert!(
+        !log_dir.join("execution_log.3.LOG").exists(),
+        "Only 2 rotated files should be kept"
+    );
+
+    // The current file has the newest messages and respects the size limit
+    let contents = fs::read_to_string(&log_file_path).unwrap();
+    assert!(contents.contains("Rotated message 19"));
+    assert!(contents.len() <= 300);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compression() {
+    use config::{CompressionFormat, CompressionMode, CompressionPolicy};
+
+    let root = test_dir("compression");
+    for folder in ["2000-01-01 00_00_00", "2020-01-01 00_00_00", "not_a_log"] {
+        fs::create_dir_all(root.join(folder)).unwrap();
+        fs::write(root.join(folder).join("execution_log.txt"), "Old message").unwrap();
+    }
+
+    let mut config = config::LoggerConfiguration::new(root.clone(), "txt", None, None, None);
+    config.set_compression(Some(CompressionPolicy {
+        format: CompressionFormat::Gzip,
+        mode: CompressionMode::Archive,
+        after_days: None,
+        after_executions: Some(2),
+    }));
+    let logger = logger::Logger::new(config).unwrap();
+
+    // Only the newest old execution and the current execution are kept uncompressed
+    assert!(root.join("2000-01-01 00_00_00.tar.gz").is_file());
+    assert!(!root.join("2000-01-01 00_00_00").exists());
+    assert!(root.join("2020-01-01 00_00_00").is_dir());
+    assert!(root.join("not_a_log/execution_log.txt").is_file());
+    assert!(logger.get_log_file_path().is_file());
+
+    // Delete executions older than 2010-01-01
+    let days_stored = (chrono::Local::now().date_naive()
+        - chrono::NaiveDate::from_ymd_opt(2010, 1, 1).unwrap())
+    .num_days() as u32;
+    let mut config =
+        config::LoggerConfiguration::new(root.clone(), "txt", Some(days_stored), None, None);
+    config.set_compression(Some(CompressionPolicy {
+        format: CompressionFormat::Zstd,
+        mode: CompressionMode::PerFile,
+        after_days: Some(1),
+        after_executions: None,
+    }));
+    let _logger = logger::Logger::new(config).unwrap();
+
+    // Archives are deleted like folders, the remaining old folder is compressed in place
+    assert!(!root.join("2000-01-01 00_00_00.tar.gz").exists());
+    assert!(root
+        .join("2020-01-01 00_00_00/execution_log.txt.zst")
+        .is_file());
+    assert!(!root.join("2020-01-01 00_00_00/execution_log.txt").exists());
+    assert!(root.join("not_a_log/execution_log.txt").is_file());
+}
+
+#[test]
+fn test_max_total_bytes() {
+    let root = test_dir("disk_usage");
+    let old_message = "x".repeat(1000);
+    for folder in [
+        "2020-01-01 00_00_00",
+        "2020-01-02 00_00_00",
+        "2020-01-03 00_00_00",
+    ] {
+        fs::create_dir_all(root.join(folder)).unwrap();
+        fs::write(root.join(folder).join("execution_log.txt"), &old_message).unwrap();
+    }
+    fs::create_dir_all(root.join("not_a_log")).unwrap();
+    fs::write(root.join("not_a_log/data.txt"), "x".repeat(5000)).unwrap();
+
+    // Links are not followed: neither the linked folder nor the loop are counted
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&root, root.join("2020-01-02 00_00_00/root")).unwrap();
+
+    let mut config = config::LoggerConfiguration::new(root.clone(), "txt", None, None, None);
+    config.set_max_total_bytes(Some(2500)).unwrap();
+    logger::Logger::new(config).unwrap();
+
+    assert!(!root.join("2020-01-01 00_00_00").exists());
+    assert!(root.join("2020-01-02 00_00_00").exists());
+    assert!(root.join("2020-01-03 00_00_00").exists());
+    assert!(root.join("not_a_log/data.txt").exists());
+
+    // The current execution is never deleted, even if the budget only fits it
+    let mut config = config::LoggerConfiguration::new(root.clone(), "txt", None, None, None);
+    config.set_max_total_bytes(Some(1)).unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+
+    assert!(!root.join("2020-01-02 00_00_00").exists());
+    assert!(!root.join("2020-01-03 00_00_00").exists());
+    assert!(logger.get_log_file_path().exists());
+}
+
+#[test]
+fn test_max_total_bytes_rotation() {
+    let root = test_dir("disk_usage_rotation");
+    fs::create_dir_all(root.join("2020-01-01 00_00_00")).unwrap();
+    fs::write(
+        root.join("2020-01-01 00_00_00/execution_log.txt"),
+        "x".repeat(500),
+    )
+    .unwrap();
+
+    let mut config = config::LoggerConfiguration::new(root.clone(), "txt", None, None, None);
+    config
+        .set_rotation_policy(config::RotationPolicy::MaxBytes(300))
+        .unwrap();
+    config.set_max_total_bytes(Some(1000)).unwrap();
+
+    // Housekeeping keeps the old execution, it fits in the limit
+    let logger = logger::Logger::new(config).unwrap();
+    assert!(root.join("2020-01-01 00_00_00").exists());
+
+    for i in 0..50 {
+        logger.info(&format!("Rotated message {}", i));
+    }
+    logger.flush();
+
+    // The rotations delete the old execution, then the oldest parts of the current one
+    let log_file_path = logger.get_log_file_path();
+    let log_dir = log_file_path.parent().unwrap();
+    let total_bytes: u64 = fs::read_dir(log_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().metadata().unwrap().len())
+        .sum();
+    assert!(!root.join("2020-01-01 00_00_00").exists());
+    assert!(log_dir.join("execution_log.1.txt").exists());
+    assert!(total_bytes <= 1000 + 300, "{} bytes", total_bytes);
+
+    let contents = fs::read_to_string(&log_file_path).unwrap();
+    assert!(contents.contains("Rotated message 49"));
+}
+
+#[test]
+fn test_json_lines() {
+    let mut config =
+        config::LoggerConfiguration::new(test_dir("json"), "jsonl", Some(7), Some(5), None);
+    config.set_output_format(config::OutputFormat::JsonLines);
+
+    let logger = logger::Logger::new(config).unwrap();
+    logger.warn("Message with \"quotes\", \\ and\na new line");
+    logger.flush();
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 2, "Each message should be a single line");
+
+    let record: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+    assert_eq!(record["level"], "WARN");
+    assert_eq!(record["severity"], 40);
+    assert_eq!(
+        record["message"],
+        "Message with \"quotes\", \\ and\na new line"
+    );
+    assert!(record["timestamp"].is_string());
+    assert!(record["exe"].is_string());
+    assert!(record["host"].is_string());
+    assert!(record["user"].is_string());
+}
+
+#[test]
+fn test_key_value_fields() {
+    let mut config = new_config("fields", Some(LogLevel::Info));
+    config
+        .set_message_format("{LEVEL} | {MESSAGE} | {FIELDS}")
+        .unwrap();
+
+    let logger = logger::Logger::new(config).unwrap();
+    let order_id = String::from("A-123");
+    logger.info_kv("Order sent", &[("order_id", &order_id), ("retries", &3)]);
+    logger.debug_kv("Order filtered", &[("order_id", &order_id)]);
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains("INFO    | Order sent | order_id=A-123 retries=3"));
+    assert!(!contents.contains("Order filtered"));
+
+    // Fields keep their types in structured formats
+    let mut config =
+        config::LoggerConfiguration::new(test_dir("fields_json"), "jsonl", Some(7), Some(5), None);
+    config.set_output_format(config::OutputFormat::JsonLines);
+
+    let logger = logger::Logger::new(config).unwrap();
+    logger.warn_kv(
+        "Order retried",
+        &[("order_id", &order_id), ("retries", &3), ("paid", &false)],
+    );
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    let line = contents.lines().last().unwrap();
+    let record: serde_json::Value = serde_json::from_str(line).unwrap();
+    assert_eq!(record["fields"]["order_id"], "A-123");
+    assert_eq!(record["fields"]["retries"], 3);
+    assert_eq!(record["fields"]["paid"], false);
+}
+
+#[test]
+fn test_macros() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Counts how many times it is formatted
+    struct CountFormat<'a>(&'a AtomicUsize);
+
+    impl std::fmt::Display for CountFormat<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            write!(f, "counted")
+        }
+    }
+
+    let _guard = lock_global_logger();
+    let config = new_config("macros", Some(LogLevel::Info));
+    log::initialize(config).unwrap();
+
+    let count = AtomicUsize::new(0);
+    exec_logger::info!("Macro message {} {}", 1, CountFormat(&count));
+    exec_logger::debug!("Macro message {} {}", 2, CountFormat(&count));
+    exec_logger::trace!("Macro message {}", CountFormat(&count));
+    exec_logger::error!("Macro message 3");
+
+    let stat = log::create_custom_level("STAT", 35);
+    exec_logger::custom!(&stat, "Macro message {}", 4);
+
+    assert_eq!(
+        count.load(Ordering::SeqCst),
+        1,
+        "Filtered messages should not be formatted"
+    );
+
+    let contents = fs::read_to_string(log::get_log_file_path().unwrap()).unwrap();
+    assert!(contents.contains("INFO    | Macro message 1 counted"));
+    assert!(!contents.contains("Macro message 2"));
+    assert!(contents.contains("ERROR   | Macro message 3"));
+    assert!(contents.contains("STAT    | Macro message 4"));
+}
+
+#[test]
+fn test_sinks() {
+    use exec_logger::record::Record;
+    use exec_logger::sink::Sink;
+    use std::sync::Arc;
+
+    // Keeps the messages in memory
+    #[derive(Default)]
+    struct MemorySink {
+        messages: Mutex<Vec<String>>,
+    }
+
+    impl Sink for MemorySink {
+        fn write(&self, _record: &Record, message: &str) {
+            self.messages.lock().unwrap().push(message.to_string());
+        }
+    }
+
+    struct ShortFormatter;
+
+    impl formatter::Formatter for ShortFormatter {
+        fn format(&self, record: &formatter::FormatterRecord, buffer: &mut String) {
+            buffer.push_str(&format!("{} {}", record.get_level(), record.get_message()));
+        }
+    }
+
+    let errors = Arc::new(MemorySink::default());
+    let all = Arc::new(MemorySink::default());
+    let verbose = Arc::new(MemorySink::default());
+
+    let mut config = new_config("sinks", Some(LogLevel::Info));
+    config.add_sink(
+        errors.clone(),
+        Some(LogLevel::Error),
+        Some(config::OutputFormat::JsonLines),
+        None,
+    );
+    config.add_sink(all.clone(), None, None, None);
+    config.add_sink(
+        verbose.clone(),
+        Some(LogLevel::Debug),
+        None,
+        Some(Arc::new(ShortFormatter)),
+    );
+
+    let logger = logger::Logger::new(config).unwrap();
+    logger.info("Sink message 1");
+    logger.debug("Sink message 2");
+    logger.error("Sink message 3");
+    logger.flush();
+
+    // Only the errors, formatted as JSON Lines
+    let errors = errors.messages.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    let record: serde_json::Value = serde_json::from_str(errors[0].trim_end()).unwrap();
+    assert_eq!(record["message"], "Sink message 3");
+
+    // Same messages as the log file, the debug message is filtered by the logger
+    let all = all.messages.lock().unwrap();
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(all.iter().any(|x| x.contains("INFO    | Sink message 1")));
+    assert!(!all.iter().any(|x| x.contains("Sink message 2")));
+    assert!(all.iter().any(|x| x.contains("ERROR   | Sink message 3")));
+    assert!(contents.ends_with(all.last().unwrap().as_str()));
+
+    // The level of a sink replaces the filter level of the logger, with its own formatter
+    let verbose = verbose.messages.lock().unwrap();
+    assert!(verbose.contains(&"DEBUG Sink message 2\n".to_string()));
+    assert_eq!(verbose.len(), all.len() + 1);
+}
+
+#[test]
+fn test_console_and_file_levels() {
+    let _guard = lock_global_logger();
+    let mut config = new_config("sink_levels", Some(LogLevel::Debug));
+    config.set_console_level(Some(LogLevel::Warn));
+    assert_eq!(config.get_console_level(), Some(LogLevel::Warn));
+    assert_eq!(config.get_file_level(), None);
+    log::initialize(config).unwrap();
+
+    log::debug("Level message 1");
+    log::set_file_level(Some(LogLevel::Warn));
+    log::info("Level message 2");
+    log::warn("Level message 3");
+    log::set_file_level(None);
+    log::debug("Level message 4");
+    log::trace("Level message 5");
+    // More verbose than the filter level
+    log::set_file_level(Some(LogLevel::Trace));
+    log::trace("Level message 6");
+
+    let contents = fs::read_to_string(log::get_log_file_path().unwrap()).unwrap();
+    assert!(contents.contains("DEBUG   | Level message 1"));
+    assert!(!contents.contains("Level message 2"));
+    assert!(contents.contains("WARN    | Level message 3"));
+    assert!(contents.contains("DEBUG   | Level message 4"));
+    assert!(!contents.contains("Level message 5"));
+    assert!(contents.contains("TRACE   | Level message 6"));
+}
+
+#[test]
+fn test_console_target() {
+    let mut config = new_config("console_target", Some(LogLevel::Info));
+    assert_eq!(config.get_console_target(), config::ConsoleTarget::Stdout);
+    config.set_console_target(config::ConsoleTarget::Split(LogLevel::Error));
+    assert_eq!(
+        config.get_console_target(),
+        config::ConsoleTarget::Split(LogLevel::Error)
+    );
+
+    // The log file is still written when the console is disabled
+    config.set_console_target(config::ConsoleTarget::Disabled);
+    let logger = logger::Logger::new(config).unwrap();
+    logger.error("Console message 1");
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains("ERROR   | Console message 1"));
+}
+
+#[test]
+fn test_colors() {
+    use exec_logger::config::{Color, ColorMode};
+
+    let mut config = new_config("colors", Some(LogLevel::Info));
+    let audit = log::create_custom_level("AUDIT", 45);
+    assert_eq!(config.get_color_mode(), ColorMode::Auto);
+    assert_eq!(config.get_level_color(&LogLevel::Error), Some(Color::Red));
+    assert_eq!(config.get_level_color(&audit), None);
+
+    config.set_level_color(&audit, Some(Color::Fixed(208)));
+    config.set_level_color(&LogLevel::Info, None);
+    assert_eq!(config.get_level_color(&audit), Some(Color::Fixed(208)));
+    assert_eq!(config.get_level_color(&LogLevel::Info), None);
+
+    // The log file never contains escape codes
+    config.set_color_mode(ColorMode::Always);
+    let logger = logger::Logger::new(config).unwrap();
+    logger.error("Color message 1");
+    logger.custom("Color message 2", &audit);
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains("ERROR   | Color message 1"));
+    assert!(contents.contains("AUDIT   | Color message 2"));
+    assert!(!contents.contains('\x1b'));
+}
+
+#[test]
+fn test_set_level() {
+    let _guard = lock_global_logger();
+    let config = new_config("set_level", Some(LogLevel::Info));
+    log::initialize(config).unwrap();
+    let log_file_path = log::get_log_file_path().unwrap();
+    assert_eq!(log::level(), Some(LogLevel::Info));
+
+    log::debug("Set level message 1");
+    log::set_level(Some(LogLevel::Debug));
+    assert_eq!(log::level(), Some(LogLevel::Debug));
+    assert!(log::enabled(&LogLevel::Debug));
+    log::debug("Set level message 2");
+    log::set_level(Some(LogLevel::Error));
+    log::warn("Set level message 3");
+    log::set_level(None);
+    assert_eq!(log::level(), None);
+    log::trace("Set level message 4");
+
+    // Same log file after changing the level
+    assert_eq!(log::get_log_file_path().unwrap(), log_file_path);
+    let contents = fs::read_to_string(log_file_path).unwrap();
+    assert!(!contents.contains("Set level message 1"));
+    assert!(contents.contains("DEBUG   | Set level message 2"));
+    assert!(!contents.contains("Set level message 3"));
+    assert!(contents.contains("TRACE   | Set level message 4"));
+
+    // A directive without target overrides the filter level
+    let mut config = new_config("set_level_directive", Some(LogLevel::Info));
+    config.set_directives("warn,my_crate=debug").unwrap();
+    log::initialize(config).unwrap();
+    assert_eq!(log::level(), Some(LogLevel::Warn));
+    log::set_level(Some(LogLevel::Debug));
+    assert_eq!(log::level(), Some(LogLevel::Debug));
+}
+
+#[test]
+fn test_directives() {
+    let mut config = new_config("directives", Some(LogLevel::Info));
+    assert!(config
+        .set_directives("warn,my_crate=info,invalid=loud")
+        .is_err());
+    assert!(config.get_directives().is_empty());
+
+    config
+        .set_directives("warn, my_crate::db=trace, my_crate::http=off")
+        .unwrap();
+    assert_eq!(config.get_directives().len(), 3);
+    config
+        .set_message_format("{LEVEL} | {TARGET} | {MESSAGE}")
+        .unwrap();
+
+    let logger = logger::Logger::new(config).unwrap();
+    assert!(logger.is_enabled_for(&LogLevel::Trace, "my_crate::db"));
+    assert!(logger.is_enabled_for(&LogLevel::Trace, "my_crate::db::pool"));
+    assert!(!logger.is_enabled_for(&LogLevel::Trace, "my_crate::dbx"));
+    assert!(!logger.is_enabled_for(&LogLevel::Error, "my_crate::http"));
+    assert!(!logger.is_enabled_for(&LogLevel::Info, "other"));
+    assert!(logger.is_enabled_for(&LogLevel::Warn, "other"));
+
+    let args = format_args!("Directive message {}", 1);
+    logger.log_args(&LogLevel::Debug, "my_crate::db", args);
+    logger.log_args(
+        &LogLevel::Error,
+        "my_crate::http",
+        format_args!("Directive message 2"),
+    );
+    // Only the directive without target applies to the methods
+    logger.info("Directive message 3");
+    logger.warn_kv("Directive message 4", &[("id", &4)]);
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains("DEBUG   | my_crate::db | Directive message 1"));
+    assert!(!contents.contains("Directive message 2"));
+    assert!(!contents.contains("Directive message 3"));
+    assert!(contents.contains("WARN    |  | Directive message 4"));
+
+    // The directive without target is used like the filter level, so the file level replaces it
+    let mut config = new_config("default_directive", None);
+    config.set_directives("info, my_crate=warn").unwrap();
+    config.set_file_level(Some(LogLevel::Debug));
+    config
+        .set_message_format("{LEVEL} | {TARGET} | {MESSAGE}")
+        .unwrap();
+
+    let logger = logger::Logger::new(config).unwrap();
+    assert!(logger.is_enabled_for(&LogLevel::Debug, "other"));
+    assert!(!logger.is_enabled_for(&LogLevel::Info, "my_crate"));
+    logger.log_args(
+        &LogLevel::Debug,
+        "other",
+        format_args!("Default directive message 1"),
+    );
+    logger.log_args(
+        &LogLevel::Info,
+        "my_crate",
+        format_args!("Default directive message 2"),
+    );
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains("DEBUG   | other | Default directive message 1"));
+    assert!(!contents.contains("Default directive message 2"));
+}
+
+#[test]
+fn test_from_env() {
+    // Each case uses its own prefix, the variables of the process are shared by the tests
+    std::env::set_var("TEST_ENV_OK_DIR", "test_files/from_env");
+    std::env::set_var("TEST_ENV_OK_LEVEL", "warn");
+    std::env::set_var("TEST_ENV_OK_DAYS", "7");
+    std::env::set_var("TEST_ENV_OK_EXECUTIONS", "3");
+    std::env::set_var("TEST_ENV_OK_FORMAT", "{LEVEL} - {MESSAGE}");
+    std::env::set_var("TEST_ENV_OK_TIMESTAMP_FORMAT", "%H:%M");
+
+    let config = config::LoggerConfiguration::from_env("TEST_ENV_OK").unwrap();
+    assert_eq!(config.get_log_dir(), PathBuf::from("test_files/from_env"));
+    assert_eq!(config.get_filter_level(), Some(LogLevel::Warn));
+    assert_eq!(config.get_days_stored(), Some(7));
+    assert_eq!(config.get_executions_stored(), Some(3));
+    assert_eq!(config.get_message_format(), "{LEVEL} - {MESSAGE}");
+    assert_eq!(config.get_timestamp_format(), "%H:%M");
+
+    // Missing variables keep the default values
+    let config = config::LoggerConfiguration::from_env("TEST_ENV_MISSING").unwrap();
+    assert_eq!(config.get_filter_level(), None);
+    assert_eq!(config.get_days_stored(), None);
+
+    std::env::set_var("TEST_ENV_DIRECTIVES_LEVEL", "info,my_crate::db=trace");
+    let config = config::LoggerConfiguration::from_env("TEST_ENV_DIRECTIVES").unwrap();
+    assert_eq!(config.get_directives().len(), 2);
+
+    // Empty variables are ignored
+    std::env::set_var("TEST_ENV_EMPTY_DIR", "");
+    std::env::set_var("TEST_ENV_EMPTY_LEVEL", " ");
+    let config = config::LoggerConfiguration::from_env("TEST_ENV_EMPTY").unwrap();
+    assert_eq!(config.get_log_dir(), PathBuf::from("./logs"));
+    assert_eq!(config.get_filter_level(), None);
+
+    std::env::set_var("TEST_ENV_DAYS_DAYS", "seven");
+    assert!(config::LoggerConfiguration::from_env("TEST_ENV_DAYS").is_err());
+
+    std::env::set_var("TEST_ENV_ZERO_EXECUTIONS", "0");
+    assert!(config::LoggerConfiguration::from_env("TEST_ENV_ZERO").is_err());
+
+    std::env::set_var("TEST_ENV_LEVEL_LEVEL", "loud");
+    assert!(config::LoggerConfiguration::from_env("TEST_ENV_LEVEL").is_err());
+
+    std::env::set_var("TEST_ENV_FORMAT_FORMAT", "{MESSAGE}");
+    assert!(config::LoggerConfiguration::from_env("TEST_ENV_FORMAT").is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_file() {
+    let dir = test_dir("config_file");
+
+    let toml_path = dir.join("logger.toml");
+    fs::write(
+        &toml_path,
+        format!("log_dir = '{}'\n", dir.join("logs").display())
+            + r#"
+days_stored = 7
+level = "audit"
+directives = "my_crate::db=trace"
+console_level = "error"
+message_format = "{LEVEL} | {MESSAGE}"
+output_format = "text"
+
+[[custom_levels]]
+name = "AUDIT"
+severity = 45
+"#,
+    )
+    .unwrap();
+
+    let config = config::LoggerConfiguration::from_file(&toml_path).unwrap();
+    let audit = LogLevel::Custom(45, "AUDIT".to_string());
+    assert_eq!(config.get_log_dir(), dir.join("logs"));
+    assert_eq!(config.get_days_stored(), Some(7));
+    assert_eq!(config.get_filter_level(), Some(audit.clone()));
+    assert_eq!(config.get_custom_level("audit"), Some(audit));
+    assert_eq!(config.get_console_level(), Some(LogLevel::Error));
+    assert_eq!(config.get_directives().len(), 1);
+    assert_eq!(config.get_message_format(), "{LEVEL} | {MESSAGE}");
+
+    // Same configuration after a round trip through JSON and YAML
+    let json_path = dir.join("logger.json");
+    fs::write(&json_path, serde_json::to_string(&config).unwrap()).unwrap();
+    let from_json = config::LoggerConfiguration::from_file(&json_path).unwrap();
+    assert_eq!(from_json.get_filter_level(), config.get_filter_level());
+    assert_eq!(from_json.get_directives(), config.get_directives());
+
+    let yaml_path = dir.join("logger.yaml");
+    fs::write(&yaml_path, "level: info\ntimestamp_format: \"%H:%M\"\n").unwrap();
+    let from_yaml = config::LoggerConfiguration::from_file(&yaml_path).unwrap();
+    assert_eq!(from_yaml.get_filter_level(), Some(LogLevel::Info));
+    assert_eq!(from_yaml.get_timestamp_format(), "%H:%M");
+
+    // Every option that can be written to a file survives a round trip
+    let mut config =
+        config::LoggerConfiguration::new(dir.join("logs"), "log", Some(3), Some(4), None);
+    let stat = config.add_custom_level("STAT", 25);
+    let verbose = LogLevel::Custom(5, "VERBOSE".to_string()); // Used without being declared
+    config.set_filter_level(verbose.clone());
+    config
+        .set_directives("my_crate::db=stat,hyper=off")
+        .unwrap();
+    config.set_console_level(Some(stat.clone()));
+    config.set_file_level(Some(LogLevel::Debug));
+    config.set_console_target(config::ConsoleTarget::Split(LogLevel::Warn));
+    config.set_color_mode(config::ColorMode::Never);
+    config.set_level_color(&LogLevel::Info, None);
+    config.set_level_color(&stat, Some(config::Color::Fixed(208)));
+    config
+        .set_message_format("{LEVEL} {TARGET} {MESSAGE}")
+        .unwrap();
+    config.set_timestamp_format("%H:%M:%S%.3f").unwrap();
+    config.set_output_format(config::OutputFormat::JsonLines);
+    config
+        .set_flush_policy(config::FlushPolicy::Interval(Duration::from_millis(250)))
+        .unwrap();
+    config
+        .set_write_mode(config::WriteMode::Async {
+            capacity: 64,
+            overflow_policy: config::OverflowPolicy::DropBelow(LogLevel::Error),
+        })
+        .unwrap();
+    config
+        .set_rotation_policy(config::RotationPolicy::MaxBytes(4096))
+        .unwrap();
+    config.set_max_rotated_files(Some(3));
+    config.set_max_total_bytes(Some(1 << 20)).unwrap();
+    #[cfg(feature = "compression")]
+    config.set_compression(Some(config::CompressionPolicy {
+        format: config::CompressionFormat::Zstd,
+        mode: config::CompressionMode::PerFile,
+        after_days: Some(2),
+        after_executions: None,
+    }));
+
+    for extension in ["toml", "json", "yaml"] {
+        let path = dir.join(format!("round_trip.{}", extension));
+        let contents = match extension {
+            "toml" => toml::to_string(&config).unwrap(),
+            "json" => serde_json::to_string(&config).unwrap(),
+            _ => serde_yaml::to_string(&config).unwrap(),
+        };
+        fs::write(&path, contents).unwrap();
+
+        let loaded = config::LoggerConfiguration::from_file(&path).unwrap();
+        assert_eq!(loaded.get_log_dir(), config.get_log_dir());
+        assert_eq!(loaded.get_file_extension(), "log");
+        assert_eq!(loaded.get_days_stored(), Some(3));
+        assert_eq!(loaded.get_executions_stored(), Some(4));
+        assert_eq!(loaded.get_filter_level(), Some(verbose.clone()));
+        assert_eq!(loaded.get_custom_level("verbose"), Some(verbose.clone()));
+        assert_eq!(loaded.get_directives(), config.get_directives());
+        assert_eq!(loaded.get_console_level(), Some(stat.clone()));
+        assert_eq!(loaded.get_file_level(), Some(LogLevel::Debug));
+        assert_eq!(loaded.get_console_target(), config.get_console_target());
+        assert_eq!(loaded.get_color_mode(), config::ColorMode::Never);
+        assert_eq!(loaded.get_level_color(&LogLevel::Info), None);
+        assert_eq!(
+            loaded.get_level_color(&LogLevel::Error),
+            config.get_level_color(&LogLevel::Error)
+        );
+        assert_eq!(
+            loaded.get_level_color(&stat),
+            Some(config::Color::Fixed(208))
+        );
+        assert_eq!(loaded.get_message_format(), config.get_message_format());
+        assert_eq!(loaded.get_timestamp_format(), "%H:%M:%S%.3f");
+        assert_eq!(loaded.get_output_format(), config::OutputFormat::JsonLines);
+        assert_eq!(loaded.get_flush_policy(), config.get_flush_policy());
+        assert_eq!(loaded.get_write_mode(), config.get_write_mode());
+        assert_eq!(loaded.get_rotation_policy(), config.get_rotation_policy());
+        assert_eq!(loaded.get_max_rotated_files(), Some(3));
+        assert_eq!(loaded.get_max_total_bytes(), Some(1 << 20));
+        #[cfg(feature = "compression")]
+        assert_eq!(loaded.get_compression(), config.get_compression());
+    }
+
+    // The values are validated like the setters
+    let invalid_path = dir.join("invalid.toml");
+    fs::write(&invalid_path, "message_format = \"{MESSAGE}\"").unwrap();
+    assert!(config::LoggerConfiguration::from_file(&invalid_path).is_err());
+    fs::write(&invalid_path, "level = \"audit\"").unwrap();
+    assert!(config::LoggerConfiguration::from_file(&invalid_path).is_err());
+    fs::write(&invalid_path, "unknown_option = 1").unwrap();
+    assert!(config::LoggerConfiguration::from_file(&invalid_path).is_err());
+    fs::write(&invalid_path, "flush_policy = { interval_ms = 0 }").unwrap();
+    assert!(config::LoggerConfiguration::from_file(&invalid_path).is_err());
+    // A zero count would delete every previous execution
+    for storage in [
+        "days_stored = 0",
+        "executions_stored = 0",
+        "file_extension = \"\"",
+    ] {
+        fs::write(&invalid_path, storage).unwrap();
+        assert!(config::LoggerConfiguration::from_file(&invalid_path).is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_watcher() {
+    use exec_logger::watcher::ConfigWatcher;
+
+    let _guard = lock_global_logger();
+    let dir = test_dir("config_watcher");
+    let path = dir.join("logger.toml");
+    let log_dir = format!("log_dir = '{}'\n", dir.join("logs").display());
+    fs::write(&path, log_dir.clone() + "level = \"info\"\n").unwrap();
+
+    // Options missing from the file keep the value set in the code
+    let mut config = config::LoggerConfiguration::from_file(&path).unwrap();
+    config.set_console_target(config::ConsoleTarget::Disabled);
+    log::initialize(config).unwrap();
+    let log_file_path = log::get_log_file_path().unwrap();
+    let watcher = ConfigWatcher::new(&path, Duration::from_millis(50)).unwrap();
+
+    log::debug("Watcher message 1");
+    fs::write(
+        &path,
+        log_dir
+            + "level = \"debug\"\nconsole_level = \"trace\"\n"
+            + "message_format = \"{LEVEL} - {MESSAGE}\"\n",
+    )
+    .unwrap();
+    sleep(Duration::from_millis(300));
+    assert_eq!(log::level(), Some(LogLevel::Debug));
+    // A console printing TRACE would enable it
+    assert!(!log::enabled(&LogLevel::Trace));
+    log::debug("Watcher message 2");
+
+    // Invalid edits keep the previous configuration
+    fs::write(&path, "level = \"loud\"\n").unwrap();
+    sleep(Duration::from_millis(300));
+    assert_eq!(log::level(), Some(LogLevel::Debug));
+    drop(watcher);
+
+    assert_eq!(log::get_log_file_path().unwrap(), log_file_path);
+    let contents = fs::read_to_string(log_file_path).unwrap();
+    assert!(!contents.contains("Watcher message 1"));
+    assert!(contents.contains("DEBUG   - Watcher message 2"));
+    assert!(contents.contains("WARN    - Invalid configuration file"));
+}
+
+#[test]
+fn test_builder() {
+    let dir = test_dir("builder");
+    let config = config::LoggerConfiguration::builder()
+        .log_dir(&dir)
+        .file_extension("log")
+        .days_stored(7)
+        .executions_stored(5)
+        .filter_level(LogLevel::Debug)
+        .console_level(LogLevel::Warn)
+        .message_format("{LEVEL} | {MESSAGE}")
+        .timestamp_format("%H:%M:%S")
+        .build()
+        .unwrap();
+    assert_eq!(config.get_log_dir(), dir);
+    assert_eq!(config.get_file_extension(), "log");
+    assert_eq!(config.get_days_stored(), Some(7));
+    assert_eq!(config.get_executions_stored(), Some(5));
+    assert_eq!(config.get_filter_level(), Some(LogLevel::Debug));
+    assert_eq!(config.get_console_level(), Some(LogLevel::Warn));
+    assert_eq!(config.get_message_format(), "{LEVEL} | {MESSAGE}");
+
+    // The filter level and the directives are applied in the order of the calls
+    let config = config::LoggerConfiguration::builder()
+        .log_dir(&dir)
+        .directives("warn,my_crate=debug")
+        .filter_level(LogLevel::Info)
+        .build()
+        .unwrap();
+    assert_eq!(config.get_filter_level(), Some(LogLevel::Info));
+    assert_eq!(config.get_directives().len(), 1);
+
+    // The log directory is checked but not created by build
+    let config = config::LoggerConfiguration::builder()
+        .log_dir(dir.join("new/logs"))
+        .build()
+        .unwrap();
+    assert!(!dir.join("new").exists());
+    assert!(logger::Logger::new(config).is_ok());
+    fs::write(dir.join("not_a_dir"), "").unwrap();
+    assert!(config::LoggerConfiguration::builder()
+        .log_dir(dir.join("not_a_dir/logs"))
+        .build()
+        .is_err());
+
+    // Every invalid value is reported
+    let result = config::LoggerConfiguration::builder()
+        .log_dir(dir.join("not_a_dir"))
+        .file_extension("")
+        .days_stored(0)
+        .executions_stored(0)
+        .message_format("{MESSAGE}")
+        .timestamp_format("%Q")
+        .flush_policy(config::FlushPolicy::Interval(Duration::ZERO))
+        .write_mode(config::WriteMode::Async {
+            capacity: 0,
+            overflow_policy: config::OverflowPolicy::Block,
+        })
+        .build();
+    match result {
+        Err(config::ConfigError::Multiple(errors)) => assert_eq!(errors.len(), 8),
+        _ => panic!("The builder should report every error"),
+    }
+}
+
+#[test]
+fn test_context() {
+    let mut config = new_config("context", Some(LogLevel::Info));
+    config
+        .set_message_format("{LEVEL} | {CONTEXT} | {MESSAGE}")
+        .unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+
+    let result = log::with_context(&[("job", &42)], || {
+        logger.info("Context message 1");
+        log::with_context(&[("request_id", &"A-1")], || {
+            logger.info("Context message 2");
+        });
+        logger.info("Context message 3");
+        "done"
+    });
+    assert_eq!(result, "done");
+    logger.info("Context message 4");
+
+    // Other threads do not share the context
+    log::with_context(&[("job", &43)], || {
+        std::thread::scope(|s| {
+            s.spawn(|| logger.info("Context message 5"));
+        });
+    });
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains("INFO    | job=42 | Context message 1"));
+    assert!(contents.contains("INFO    | job=42 request_id=A-1 | Context message 2"));
+    assert!(contents.contains("INFO    | job=42 | Context message 3"));
+    assert!(contents.contains("INFO    |  | Context message 4"));
+    assert!(contents.contains("INFO    |  | Context message 5"));
+
+    // Written as JSON fields
+    let mut config = new_config("context_json", Some(LogLevel::Info));
+    config.set_output_format(config::OutputFormat::JsonLines);
+    let logger = logger::Logger::new(config).unwrap();
+    log::with_context(&[("job", &42), ("request_id", &"A-1")], || {
+        logger.info_kv("Context message 6", &[("retries", &3)]);
+    });
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    let line = contents.lines().last().unwrap();
+    let record: serde_json::Value = serde_json::from_str(line).unwrap();
+    assert_eq!(record["context"]["job"], 42);
+    assert_eq!(record["context"]["request_id"], "A-1");
+    assert_eq!(record["fields"]["retries"], 3);
+}
+
+#[test]
+fn test_thread_keywords() {
+    let mut config = new_config("thread_keywords", Some(LogLevel::Info));
+    assert!(config
+        .set_message_format("{LEVEL} {MESSAGE} {THREAD}")
+        .is_err());
+    config
+        .set_message_format(
+            "{THREAD_NAME} | {THREAD_ID} | {PID} | {EXECUTION_ID} | {ELAPSED} | {LEVEL} | {MESSAGE}",
+        )
+        .unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+
+    std::thread::scope(|s| {
+        std::thread::Builder::new()
+            .name("worker-1".to_string())
+            .spawn_scoped(s, || logger.info("Thread message 1"))
+            .unwrap();
+    });
+
+    let execution_id = logger
+        .get_log_file_path()
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    let line = contents
+        .lines()
+        .find(|x| x.contains("Thread message 1"))
+        .unwrap();
+    let parts: Vec<&str> = line.split(" | ").collect();
+    assert_eq!(parts[0], "worker-1");
+    assert!(parts[1].parse::<u64>().is_ok());
+    assert_eq!(parts[2], std::process::id().to_string());
+    assert_eq!(parts[3], execution_id);
+    assert!(parts[4].parse::<f64>().unwrap() >= 0.0);
+}
+
+#[test]
+fn test_source_location() {
+    let mut config = new_config("source_location", Some(LogLevel::Info));
+    config
+        .set_message_format("{FILE}:{LINE} | {MODULE} | {LEVEL} | {MESSAGE}")
+        .unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+
+    let line = line!() + 1;
+    logger.info("Location message 1");
+    logger.warn_kv("Location message 2", &[("retries", &3)]);
+    let args_line = line!() + 1;
+    logger.log_args(
+        &LogLevel::Error,
+        module_path!(),
+        format_args!("Location message {}", 3),
+    );
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains(&format!(
+        "{}:{} |  | INFO    | Location message 1",
+        file!(),
+        line
+    )));
+    assert!(contents.contains(&format!(
+        "{}:{} |  | WARN    | Location message 2",
+        file!(),
+        line + 1
+    )));
+    assert!(contents.contains(&format!(
+        "{}:{} | {} | ERROR   | Location message 3",
+        file!(),
+        args_line,
+        module_path!()
+    )));
+}
+
+#[test]
+fn test_message_template() {
+    let mut config = new_config("message_template", Some(LogLevel::Info));
+    match config.set_message_format("{LEVEL} | {MESAGE}") {
+        Err(config::ConfigError::UnknownKeyword { keyword, position }) => {
+            assert_eq!(keyword, "MESAGE");
+            assert_eq!(position, 10);
+        }
+        x => panic!("Unexpected result {:?}", x),
+    }
+    assert!(config.set_message_format("{LEVEL} | {MESSAGE").is_err());
+    assert!(config.set_message_format("{LEVEL} } {MESSAGE}").is_err());
+    assert!(config.set_message_format("{LEVEL:<x} {MESSAGE}").is_err());
+    assert!(config.set_message_format("{{LEVEL}} {MESSAGE}").is_err());
+    config
+        .set_message_format("{{{LEVEL:>6}}} [{PID:^12}] {MESSAGE} {{done}}")
+        .unwrap();
+    let logger = logger::Logger::new(config).unwrap();
+
+    logger.info("Template message 1 {LEVEL} {MESSAGE}");
+    logger.error("Template message 2");
+
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    let pid = format!("{:^12}", std::process::id());
+    assert!(contents.contains(&format!(
+        "{{  INFO}} [{}] Template message 1 {{LEVEL}} {{MESSAGE}} {{done}}",
+        pid
+    )));
+    assert!(contents.contains(&format!("{{ ERROR}} [{}] Template message 2 {{done}}", pid)));
+}
+
+#[test]
+fn test_formatter() {
+    // Writes the records as CSV, the fields are appended as key=value
+    struct CsvFormatter;
+
+    impl formatter::Formatter for CsvFormatter {
+        fn format(&self, record: &formatter::FormatterRecord, buffer: &mut String) {
+            buffer.push_str(&format!(
+                "{},{},{},{}",
+                record.get_exe_name(),
+                record.get_execution_id(),
+                record.get_level(),
+                record.get_message()
+            ));
+            for (key, value) in record.get_fields() {
+                buffer.push_str(&format!(",{}={}", key, value));
+            }
+        }
+    }
+
+    let mut config = new_config("formatter", Some(LogLevel::Info));
+    config.set_formatter(Some(Box::new(CsvFormatter)));
+    let exe_name = config.get_exe_name().to_string();
+    let logger = logger::Logger::new(config).unwrap();
+
+    logger.info("Formatter message 1");
+    logger.warn_kv(
+        "Formatter message 2",
+        &[("order_id", &"A-123"), ("retries", &3)],
+    );
+
+    let execution_id = logger
+        .get_log_file_path()
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let contents = fs::read_to_string(logger.get_log_file_path()).unwrap();
+    assert!(contents.contains(&format!(
+        "{},{},INFO,Formatter message 1\n",
+        exe_name, execution_id
+    )));
+    assert!(contents.contains(&format!(
+        "{},{},WARN,Formatter message 2,order_id=A-123,retries=3\n",
+        exe_name, execution_id
+    )));
+}